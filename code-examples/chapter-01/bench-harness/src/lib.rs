@@ -0,0 +1,360 @@
+//! chapter-01 のハンズオンで共通に使うベンチマークランナー
+//!
+//! `main` で `Instant::now()` を1回だけ測る方法では、ウォームアップもばらつきも
+//! 分からず、実行ごとの数字を比べられない。このランナーは
+//!
+//! 1. ウォームアップを兼ねて1回あたりの時間を見積もり、
+//! 2. 1サンプルが十分な長さになるよう繰り返し回数をまとめて、
+//! 3. 複数のサンプルから外れ値を除き、平均・中央値・標準偏差・95%信頼区間を出す。
+//!
+//! 結果は標準出力に表で出し、`--json <FILE>` を付けるとJSONでも書き出す。
+//!
+//! ```no_run
+//! use bench_harness::Runner;
+//! use std::hint::black_box;
+//!
+//! let mut runner = Runner::from_args("example");
+//! runner.bench("sum", 1000, || (0..black_box(1000u64)).sum::<u64>());
+//! runner.finish();
+//! ```
+
+use serde::Serialize;
+use std::fmt::Display;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub mod stats;
+
+pub use stats::Summary;
+
+pub const USAGE: &str = "Options:
+  --json <FILE>    also write the results as JSON
+  --samples <N>    number of samples per benchmark (default: 30)
+  --quick          shorter warmup and fewer samples, for a rough look";
+
+/// 計測の設定
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Config {
+    /// この時間だけ実行してから計測を始める
+    #[serde(serialize_with = "as_millis")]
+    pub warmup_time: Duration,
+    /// 1サンプルの目安の長さ。短い処理はこの長さになるまで繰り返して1サンプルにする
+    #[serde(serialize_with = "as_millis")]
+    pub sample_time: Duration,
+    pub samples: usize,
+    /// 1つのベンチマークに使う時間の上限。遅い処理はサンプル数を減らす（`min_samples` まで）
+    #[serde(serialize_with = "as_millis")]
+    pub max_time: Duration,
+    pub min_samples: usize,
+    /// 四分位範囲のこの倍数より外側を外れ値として除く
+    pub outlier_k: f64,
+}
+
+fn as_millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            warmup_time: Duration::from_millis(500),
+            sample_time: Duration::from_millis(10),
+            samples: 30,
+            max_time: Duration::from_secs(5),
+            min_samples: 5,
+            outlier_k: 1.5,
+        }
+    }
+}
+
+impl Config {
+    /// おおまかに見るための短い設定
+    pub fn quick() -> Self {
+        Config {
+            warmup_time: Duration::from_millis(50),
+            samples: 10,
+            max_time: Duration::from_secs(1),
+            min_samples: 3,
+            ..Config::default()
+        }
+    }
+}
+
+/// コマンドライン引数から作る実行時のオプション
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub config: Config,
+    pub json: Option<PathBuf>,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            config: Config::default(),
+            json: None,
+        };
+        let mut samples = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => options.json = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+                "--samples" => {
+                    let value = args.next().ok_or(USAGE)?;
+                    samples = Some(value.parse::<usize>().ok().filter(|&n| n > 0).ok_or_else(|| {
+                        format!("--samples must be a positive integer, got `{}`", value)
+                    })?);
+                }
+                "--quick" => options.config = Config::quick(),
+                _ => return Err(USAGE.to_string()),
+            }
+        }
+        // `--quick` の前後どちらに書いても効くように最後に反映する
+        if let Some(samples) = samples {
+            options.config.samples = samples;
+            options.config.min_samples = options.config.min_samples.min(samples);
+        }
+        Ok(options)
+    }
+}
+
+/// 1つのベンチマークの結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Measurement {
+    pub name: String,
+    pub parameter: Option<String>,
+    pub iterations_per_sample: u64,
+    /// 外れ値を除いたサンプル数
+    pub samples: usize,
+    pub outliers: usize,
+    #[serde(flatten)]
+    pub summary: Summary,
+    /// 外れ値を除く前の、各サンプルの1回あたりの時間
+    pub samples_ns: Vec<f64>,
+}
+
+impl Measurement {
+    /// `name/parameter` の形の名前
+    pub fn id(&self) -> String {
+        match &self.parameter {
+            Some(parameter) => format!("{}/{}", self.name, parameter),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// JSONに書き出す実行全体の結果
+#[derive(Debug, Serialize)]
+pub struct SuiteReport<'a> {
+    pub suite: &'a str,
+    /// UNIX時刻（秒）
+    pub timestamp: u64,
+    pub os: &'static str,
+    pub arch: &'static str,
+    /// 最適化なし（debug）でビルドされているか。debugの数字は比較に使えない
+    pub debug_build: bool,
+    pub config: &'a Config,
+    pub results: &'a [Measurement],
+}
+
+/// ベンチマークを順に実行して結果を集める
+pub struct Runner {
+    suite: String,
+    config: Config,
+    json: Option<PathBuf>,
+    results: Vec<Measurement>,
+}
+
+/// ナノ秒を読みやすい単位にする
+pub fn format_ns(ns: f64) -> String {
+    if !ns.is_finite() {
+        return "n/a".to_string();
+    }
+    let (value, unit) = match ns.abs() {
+        x if x < 1e3 => (ns, "ns"),
+        x if x < 1e6 => (ns / 1e3, "µs"),
+        x if x < 1e9 => (ns / 1e6, "ms"),
+        _ => (ns / 1e9, "s"),
+    };
+    format!("{:.2} {}", value, unit)
+}
+
+impl Runner {
+    pub fn new(suite: &str, options: Options) -> Runner {
+        Runner {
+            suite: suite.to_string(),
+            config: options.config,
+            json: options.json,
+            results: Vec::new(),
+        }
+    }
+
+    /// `std::env::args()` のオプション（`USAGE` を参照）で作る。不正な引数なら使い方を表示して終了する
+    pub fn from_args(suite: &str) -> Runner {
+        match Options::parse(std::env::args().skip(1)) {
+            Ok(options) => Runner::new(suite, options),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn results(&self) -> &[Measurement] {
+        &self.results
+    }
+
+    /// `f` を計測して結果を表示する。`f` の戻り値は最適化で消されないよう `black_box` に通す
+    pub fn bench<T>(&mut self, name: &str, parameter: impl Display, f: impl FnMut() -> T) -> &Measurement {
+        let parameter = parameter.to_string();
+        let parameter = (!parameter.is_empty()).then_some(parameter);
+        let measurement = measure(&self.config, name, parameter, f);
+        print_measurement(&measurement);
+        self.results.push(measurement);
+        self.results.last().unwrap()
+    }
+
+    /// JSONの出力先が指定されていれば書き出す
+    pub fn finish(self) {
+        if cfg!(debug_assertions) {
+            eprintln!("[bench] warning: built without optimizations; run with --release for comparable numbers");
+        }
+        let Some(path) = &self.json else {
+            return;
+        };
+        let report = SuiteReport {
+            suite: &self.suite,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            debug_build: cfg!(debug_assertions),
+            config: &self.config,
+            results: &self.results,
+        };
+        let written = serde_json::to_string_pretty(&report)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(path, json + "\n"));
+        match written {
+            Ok(()) => println!("[bench] results written to {}", path.display()),
+            Err(e) => {
+                eprintln!("[bench] failed to write {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn measure<T>(config: &Config, name: &str, parameter: Option<String>, mut f: impl FnMut() -> T) -> Measurement {
+    // ウォームアップしながら1回あたりの時間を見積もる（少なくとも1回は実行する）
+    let start = Instant::now();
+    let mut warmup_iterations: u64 = 0;
+    loop {
+        black_box(f());
+        warmup_iterations += 1;
+        if start.elapsed() >= config.warmup_time {
+            break;
+        }
+    }
+    let estimate_ns = (start.elapsed().as_nanos() as f64 / warmup_iterations as f64).max(1.0);
+
+    let iterations = (config.sample_time.as_nanos() as f64 / estimate_ns).ceil().max(1.0) as u64;
+    let sample_ns = estimate_ns * iterations as f64;
+    let affordable = (config.max_time.as_nanos() as f64 / sample_ns) as usize;
+    let samples = affordable.clamp(config.min_samples.min(config.samples), config.samples);
+
+    let samples_ns: Vec<f64> = (0..samples)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                black_box(f());
+            }
+            start.elapsed().as_nanos() as f64 / iterations as f64
+        })
+        .collect();
+
+    let (kept, outliers) = stats::reject_outliers(&samples_ns, config.outlier_k);
+    Measurement {
+        name: name.to_string(),
+        parameter,
+        iterations_per_sample: iterations,
+        samples: kept.len(),
+        outliers,
+        summary: Summary::new(&kept),
+        samples_ns,
+    }
+}
+
+fn print_measurement(measurement: &Measurement) {
+    let summary = &measurement.summary;
+    let outliers = match measurement.outliers {
+        0 => String::new(),
+        1 => ", 1 outlier".to_string(),
+        n => format!(", {} outliers", n),
+    };
+    println!(
+        "  {:<32} {:>10} ± {:<10} median {:>10}  σ {:>10}  (n={}{})",
+        measurement.id(),
+        format_ns(summary.mean_ns),
+        format_ns((summary.ci_high_ns - summary.ci_low_ns) / 2.0),
+        format_ns(summary.median_ns),
+        format_ns(summary.stddev_ns),
+        measurement.samples,
+        outliers
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_options() {
+        let options = Options::parse(args(&["--samples", "3", "--quick", "--json", "out.json"])).unwrap();
+        assert_eq!(options.config.samples, 3);
+        assert_eq!(options.config.warmup_time, Config::quick().warmup_time);
+        assert_eq!(options.json, Some(PathBuf::from("out.json")));
+
+        assert!(Options::parse(args(&["--samples", "0"])).unwrap_err().contains("positive"));
+        assert_eq!(Options::parse(args(&["--verbose"])).unwrap_err(), USAGE);
+    }
+
+    #[test]
+    fn test_format_ns() {
+        assert_eq!(format_ns(12.0), "12.00 ns");
+        assert_eq!(format_ns(1_500.0), "1.50 µs");
+        assert_eq!(format_ns(2_500_000.0), "2.50 ms");
+        assert_eq!(format_ns(3e9), "3.00 s");
+    }
+
+    #[test]
+    fn test_bench_collects_samples() {
+        let config = Config {
+            warmup_time: Duration::from_millis(1),
+            sample_time: Duration::from_micros(100),
+            samples: 8,
+            max_time: Duration::from_millis(50),
+            min_samples: 2,
+            outlier_k: 1.5,
+        };
+        let mut runner = Runner::new("test", Options { config, json: None });
+        let mut calls = 0u64;
+        let measurement = runner.bench("count", 10, || {
+            calls += 1;
+            (0..black_box(10u64)).sum::<u64>()
+        });
+        assert_eq!(measurement.id(), "count/10");
+        assert_eq!(measurement.samples_ns.len(), 8);
+        assert_eq!(measurement.samples + measurement.outliers, 8);
+        assert!(measurement.summary.min_ns <= measurement.summary.median_ns);
+        // ウォームアップ + 8サンプル分は呼ばれている
+        assert!(calls > 8 * measurement.iterations_per_sample);
+
+        let json = serde_json::to_value(runner.results()).unwrap();
+        assert_eq!(json[0]["parameter"], "10");
+        assert!(json[0]["mean_ns"].as_f64().unwrap() > 0.0);
+    }
+}
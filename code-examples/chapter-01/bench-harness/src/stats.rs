@@ -0,0 +1,127 @@
+// 計測値の統計
+//
+// 外れ値はTukeyの方法（四分位範囲の k 倍より外側）で除き、残りから平均・中央値・
+// 標準偏差と、平均の信頼区間（t分布）を求める。
+
+use serde::Serialize;
+
+/// 1つのベンチマークの統計（単位はすべて1回あたりのナノ秒）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub mean_ns: f64,
+    pub median_ns: f64,
+    /// 標本標準偏差（n - 1 で割る）
+    pub stddev_ns: f64,
+    pub min_ns: f64,
+    pub max_ns: f64,
+    /// 平均の95%信頼区間
+    pub ci_low_ns: f64,
+    pub ci_high_ns: f64,
+}
+
+/// 並べ替え済みの値の分位点（線形補間）
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    assert!(!sorted.is_empty(), "quantile of an empty slice");
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Tukeyの柵の外側にある値を除く。戻り値は（残った値、除いた数）
+pub fn reject_outliers(samples: &[f64], k: f64) -> (Vec<f64>, usize) {
+    // 四分位数が意味を持つだけの数がなければ何もしない
+    if samples.len() < 4 {
+        return (samples.to_vec(), 0);
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = quantile(&sorted, 0.25);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let (low, high) = (q1 - k * iqr, q3 + k * iqr);
+    let kept: Vec<f64> = samples.iter().copied().filter(|&x| x >= low && x <= high).collect();
+    let rejected = samples.len() - kept.len();
+    (kept, rejected)
+}
+
+// 両側95%のt分布の臨界値（自由度1〜30）。それより大きければ正規分布で近似する
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+    2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+fn t_critical(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        df if df <= T_95.len() => T_95[df - 1],
+        _ => 1.96,
+    }
+}
+
+impl Summary {
+    pub fn new(samples: &[f64]) -> Summary {
+        assert!(!samples.is_empty(), "no samples to summarize");
+        let n = samples.len();
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        // 標本が1つなら区間は出せないので、平均そのものにする
+        let half_width = if n > 1 { t_critical(n - 1) * stddev / (n as f64).sqrt() } else { 0.0 };
+
+        Summary {
+            mean_ns: mean,
+            median_ns: quantile(&sorted, 0.5),
+            stddev_ns: stddev,
+            min_ns: sorted[0],
+            max_ns: sorted[n - 1],
+            ci_low_ns: mean - half_width,
+            ci_high_ns: mean + half_width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantile() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(quantile(&sorted, 0.0), 1.0);
+        assert_eq!(quantile(&sorted, 0.5), 2.5);
+        assert_eq!(quantile(&sorted, 1.0), 4.0);
+        assert_eq!(quantile(&[7.0], 0.25), 7.0);
+    }
+
+    #[test]
+    fn test_reject_outliers() {
+        let samples = [10.0, 11.0, 10.5, 9.5, 10.2, 100.0, 9.8];
+        let (kept, rejected) = reject_outliers(&samples, 1.5);
+        assert_eq!(rejected, 1);
+        assert!(!kept.contains(&100.0));
+        // 少なすぎるときは除かない
+        assert_eq!(reject_outliers(&[1.0, 100.0], 1.5), (vec![1.0, 100.0], 0));
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::new(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(summary.mean_ns, 5.0);
+        assert_eq!(summary.median_ns, 4.5);
+        assert!((summary.stddev_ns - 2.138_089_935).abs() < 1e-6);
+        // 自由度7の t = 2.365
+        let half_width = 2.365 * summary.stddev_ns / 8f64.sqrt();
+        assert!((summary.ci_high_ns - (5.0 + half_width)).abs() < 1e-9);
+        assert_eq!((summary.min_ns, summary.max_ns), (2.0, 9.0));
+
+        let single = Summary::new(&[3.0]);
+        assert_eq!((single.stddev_ns, single.ci_low_ns, single.ci_high_ns), (0.0, 3.0, 3.0));
+    }
+}
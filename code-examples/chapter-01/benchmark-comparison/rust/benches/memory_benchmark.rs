@@ -0,0 +1,23 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use performance_comparison::{generate, process};
+
+// `benchmark` バイナリと同じ処理を criterion で計測する
+fn processing_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("benchmark_comparison");
+    
+    for size in [1_000, 10_000, 100_000] {
+        group.bench_with_input(BenchmarkId::new("generate", size), &size, |b, &size| {
+            b.iter(|| generate(black_box(size)))
+        });
+        
+        let data = generate(size);
+        group.bench_with_input(BenchmarkId::new("filter_map_sum", size), &data, |b, data| {
+            b.iter(|| process(black_box(data)))
+        });
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, processing_benchmark);
+criterion_main!(benches);
@@ -1,29 +1,22 @@
-use std::time::Instant;
+use bench_harness::Runner;
+use performance_comparison::{generate, process};
+use std::hint::black_box;
 
-fn benchmark_rust() {
+fn benchmark_rust(runner: &mut Runner) {
     let sizes = vec![1_000, 10_000, 100_000];
     
     for size in sizes {
-        let start = Instant::now();
+        let data = generate(size);
         
-        // データ生成
-        let data: Vec<i32> = (0..size).collect();
-        
-        // 処理: 偶数のみフィルタ → 2倍 → 合計
-        let result: i32 = data.iter()
-            .filter(|&&x| x % 2 == 0)
-            .map(|&x| x * 2)
-            .sum();
-        
-        let elapsed = start.elapsed();
-        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
-        
-        println!("Size: {}, Result: {}, Time: {:.2} ms", 
-            size, result, elapsed_ms);
+        println!("Size: {}, Result: {}", size, process(&data));
+        runner.bench("generate", size, || generate(black_box(size)));
+        runner.bench("filter_map_sum", size, || process(black_box(&data)));
     }
 }
 
 fn main() {
     println!("=== Rust Benchmark ===");
-    benchmark_rust();
-}
\ No newline at end of file
+    let mut runner = Runner::from_args("benchmark-comparison");
+    benchmark_rust(&mut runner);
+    runner.finish();
+}
@@ -0,0 +1,16 @@
+// ベンチマークの処理（`benchmark` バイナリと criterion のベンチマークで共有する）
+
+/// データ生成: 0..size
+pub fn generate(size: i32) -> Vec<i32> {
+    (0..size).collect()
+}
+
+/// 処理: 偶数のみフィルタ → 2倍 → 合計
+///
+/// C++版と同じく合計は64bitで持つ。i32では100,000件で溢れる。
+pub fn process(data: &[i32]) -> i64 {
+    data.iter()
+        .filter(|&&x| x % 2 == 0)
+        .map(|&x| x as i64 * 2)
+        .sum()
+}
@@ -0,0 +1,147 @@
+// 評価項目とプロジェクトごとの重み
+
+use serde::{Deserialize, Serialize};
+
+/// 言語を評価する項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criterion {
+    Performance,
+    DevelopmentSpeed,
+    Maintenance,
+    Risk,
+    /// ライブラリ・ツール・コミュニティの成熟度
+    EcosystemMaturity,
+    /// その言語の経験者を採用しやすいか
+    HiringPool,
+}
+
+impl Criterion {
+    pub const ALL: [Criterion; 6] = [
+        Criterion::Performance,
+        Criterion::DevelopmentSpeed,
+        Criterion::Maintenance,
+        Criterion::Risk,
+        Criterion::EcosystemMaturity,
+        Criterion::HiringPool,
+    ];
+
+    /// ルールファイルや重みの設定で使う名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Criterion::Performance => "performance",
+            Criterion::DevelopmentSpeed => "development_speed",
+            Criterion::Maintenance => "maintenance",
+            Criterion::Risk => "risk",
+            Criterion::EcosystemMaturity => "ecosystem_maturity",
+            Criterion::HiringPool => "hiring_pool",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Criterion::Performance => "Performance",
+            Criterion::DevelopmentSpeed => "Dev Speed",
+            Criterion::Maintenance => "Maintenance",
+            Criterion::Risk => "Risk",
+            Criterion::EcosystemMaturity => "Ecosystem",
+            Criterion::HiringPool => "Hiring",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 総合スコアを出すときの評価項目ごとの重み
+///
+/// 総合スコアは重み付き平均なので、全体を何倍しても結果は変わらない。
+/// デフォルトは従来どおり4項目の単純平均で、追加の項目は重み0（使わない）。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub performance: f64,
+    pub development_speed: f64,
+    pub maintenance: f64,
+    pub risk: f64,
+    pub ecosystem_maturity: f64,
+    pub hiring_pool: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            performance: 1.0,
+            development_speed: 1.0,
+            maintenance: 1.0,
+            risk: 1.0,
+            ecosystem_maturity: 0.0,
+            hiring_pool: 0.0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn get(&self, criterion: Criterion) -> f64 {
+        match criterion {
+            Criterion::Performance => self.performance,
+            Criterion::DevelopmentSpeed => self.development_speed,
+            Criterion::Maintenance => self.maintenance,
+            Criterion::Risk => self.risk,
+            Criterion::EcosystemMaturity => self.ecosystem_maturity,
+            Criterion::HiringPool => self.hiring_pool,
+        }
+    }
+
+    /// 重みは0以上の有限値で、少なくとも1つは正でなければならない
+    pub fn validate(&self) -> Result<(), String> {
+        for criterion in Criterion::ALL {
+            let weight = self.get(criterion);
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("weight of `{}` must be a non-negative number, got {}", criterion.name(), weight));
+            }
+        }
+        if self.total() <= 0.0 {
+            return Err("at least one weight must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn total(&self) -> f64 {
+        Criterion::ALL.iter().map(|&c| self.get(c)).sum()
+    }
+
+    /// 項目ごとのスコアの重み付き平均
+    pub fn weighted_average(&self, scores: &[f64; Criterion::ALL.len()]) -> f64 {
+        let sum: f64 = Criterion::ALL.iter().map(|&c| self.get(c) * scores[c.index()]).sum();
+        sum / self.total()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_weights_average_original_criteria() {
+        let scores = [9.0, 6.0, 9.0, 8.0, 1.0, 1.0];
+        assert_eq!(Weights::default().weighted_average(&scores), 8.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Weights::default().validate().is_ok());
+        let weights = Weights {
+            risk: -1.0,
+            ..Weights::default()
+        };
+        assert!(weights.validate().unwrap_err().contains("`risk`"));
+
+        let zero: Weights = serde_json::from_str(
+            r#"{"performance": 0, "development_speed": 0, "maintenance": 0, "risk": 0}"#,
+        )
+        .unwrap();
+        assert!(zero.validate().is_err());
+    }
+}
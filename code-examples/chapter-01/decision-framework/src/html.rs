@@ -0,0 +1,314 @@
+// 1ファイルで完結するHTMLレポート
+//
+// 外部のCSSやスクリプトを読まないので、そのままADR（Architecture Decision
+// Record）に添付できる。レーダーチャートはインラインのSVGで描く。
+
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use crate::criteria::Criterion;
+use crate::portfolio::SystemEvaluation;
+use crate::report::{self, Evaluation, Report};
+use crate::rules::MAX_SCORE;
+
+// 言語ごとの線の色（言語がもっと多ければ繰り返す）
+const PALETTE: [&str; 8] = [
+    "#d62728", "#1f77b4", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+td.num { text-align: right; }
+.muted { color: #777; }
+.swatch { display: inline-block; width: 0.8em; height: 0.8em; margin-right: 0.4em; }
+.chart { display: flex; gap: 2em; align-items: center; }";
+
+// チャートの大きさ
+const SIZE: f64 = 400.0;
+const RADIUS: f64 = 140.0;
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn render(reports: &[Report]) -> String {
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>Technology decision report</title>").unwrap();
+    writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(out, "<h1>Technology decision report</h1>").unwrap();
+    for report in reports {
+        match report {
+            Report::Project(evaluation) => render_evaluation(&mut out, evaluation),
+            Report::System(system) => render_system(&mut out, system),
+        }
+    }
+    writeln!(out, "</body>\n</html>").unwrap();
+    out
+}
+
+// 重みのある項目をチャートの軸にする。3本未満では多角形にならないので全項目を使う
+fn chart_axes(evaluation: &Evaluation) -> Vec<Criterion> {
+    let weighted: Vec<Criterion> = Criterion::ALL
+        .into_iter()
+        .filter(|&c| evaluation.weights.get(c) > 0.0)
+        .collect();
+    if weighted.len() >= 3 {
+        weighted
+    } else {
+        Criterion::ALL.to_vec()
+    }
+}
+
+// `axis` 本目の軸上で、中心から `ratio`（0〜1）の位置。最初の軸は真上
+fn point(axis: usize, axes: usize, ratio: f64) -> (f64, f64) {
+    let angle = -PI / 2.0 + 2.0 * PI * axis as f64 / axes as f64;
+    let center = SIZE / 2.0;
+    (center + RADIUS * ratio * angle.cos(), center + RADIUS * ratio * angle.sin())
+}
+
+fn polygon_points(ratios: &[f64]) -> String {
+    ratios
+        .iter()
+        .enumerate()
+        .map(|(i, &ratio)| {
+            let (x, y) = point(i, ratios.len(), ratio);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn radar_chart(evaluation: &Evaluation) -> String {
+    let axes = chart_axes(evaluation);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg viewBox=\"0 0 {size} {size}\" width=\"{size}\" height=\"{size}\" role=\"img\" aria-label=\"Scores of {}\">",
+        escape(&evaluation.scenario),
+        size = SIZE
+    )
+    .unwrap();
+
+    // 目盛り（2点ごと）と軸
+    for step in 1..=5 {
+        let ratio = step as f64 / 5.0;
+        let ring = polygon_points(&vec![ratio; axes.len()]);
+        writeln!(svg, "<polygon points=\"{}\" fill=\"none\" stroke=\"#ddd\"/>", ring).unwrap();
+    }
+    for (i, criterion) in axes.iter().enumerate() {
+        let (x, y) = point(i, axes.len(), 1.0);
+        let (lx, ly) = point(i, axes.len(), 1.15);
+        let center = SIZE / 2.0;
+        writeln!(svg, "<line x1=\"{c}\" y1=\"{c}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#ccc\"/>", x, y, c = center).unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"12\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+            lx,
+            ly,
+            escape(criterion.label())
+        )
+        .unwrap();
+    }
+
+    // 下位の言語から描いて、1位を一番上に重ねる
+    for (i, score) in evaluation.ranking.iter().enumerate().rev() {
+        // NaN は中心に置く
+        let ratios: Vec<f64> = axes
+            .iter()
+            .map(|&c| score.score(c))
+            .map(|s| if s.is_finite() { (s / MAX_SCORE).clamp(0.0, 1.0) } else { 0.0 })
+            .collect();
+        let color = PALETTE[i % PALETTE.len()];
+        writeln!(
+            svg,
+            "<polygon points=\"{}\" fill=\"{c}\" fill-opacity=\"0.12\" stroke=\"{c}\" stroke-width=\"2\"><title>{}</title></polygon>",
+            polygon_points(&ratios),
+            escape(&score.language),
+            c = color
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn render_evaluation(out: &mut String, evaluation: &Evaluation) {
+    let ranks = report::ranks(&evaluation.ranking);
+    let axes = chart_axes(evaluation);
+
+    writeln!(out, "<section>\n<h2>{}</h2>", escape(&evaluation.scenario)).unwrap();
+
+    // チャートと凡例
+    writeln!(out, "<div class=\"chart\">\n{}", radar_chart(evaluation)).unwrap();
+    writeln!(out, "<ol>").unwrap();
+    for (i, score) in evaluation.ranking.iter().enumerate() {
+        let tied = if report::is_tied(&ranks, i) { " (tied)" } else { "" };
+        writeln!(
+            out,
+            "<li><span class=\"swatch\" style=\"background: {}\"></span>{} — {}{}</li>",
+            PALETTE[i % PALETTE.len()],
+            escape(&score.language),
+            report::format_score(score.total_score),
+            tied
+        )
+        .unwrap();
+    }
+    writeln!(out, "</ol>\n</div>").unwrap();
+
+    // スコアの一覧
+    writeln!(out, "<table>\n<tr><th>Rank</th><th>Language</th><th>Score</th>").unwrap();
+    for &criterion in &axes {
+        writeln!(out, "<th>{} (×{})</th>", escape(criterion.label()), evaluation.weights.get(criterion)).unwrap();
+    }
+    writeln!(out, "</tr>").unwrap();
+    for (i, score) in evaluation.ranking.iter().enumerate() {
+        let rank = ranks[i].map_or("-".to_string(), |rank| rank.to_string());
+        write!(
+            out,
+            "<tr><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td>",
+            rank,
+            escape(&score.language),
+            report::format_score(score.total_score)
+        )
+        .unwrap();
+        for &criterion in &axes {
+            write!(out, "<td class=\"num\">{}</td>", report::format_score(score.score(criterion))).unwrap();
+        }
+        writeln!(out, "</tr>").unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    // 各スコアの根拠
+    writeln!(out, "<h3>Rationale</h3>").unwrap();
+    for score in &evaluation.ranking {
+        writeln!(out, "<h4>{}</h4>", escape(&score.language)).unwrap();
+        writeln!(out, "<table>\n<tr><th>Criterion</th><th>Score</th><th>Rule</th><th>Conditions</th><th>Reason</th></tr>").unwrap();
+        for &criterion in &axes {
+            let explanation = score.explanation(criterion);
+            let rule = explanation.rule.map_or("default".to_string(), |i| format!("rules[{}]", i));
+            let conditions: Vec<String> = explanation
+                .when
+                .iter()
+                .map(|fact| format!("<code>{}</code> (actual: {})", escape(&fact.condition), escape(&fact.actual)))
+                .collect();
+            let reason = match &explanation.reason {
+                Some(reason) => escape(reason),
+                None => "<span class=\"muted\">—</span>".to_string(),
+            };
+            writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(criterion.label()),
+                report::format_score(score.score(criterion)),
+                rule,
+                conditions.join("<br>"),
+                reason
+            )
+            .unwrap();
+        }
+        writeln!(out, "</table>").unwrap();
+    }
+
+    if let Some(leader) = evaluation.ranking.first() {
+        writeln!(out, "<h3>What would change the top choice ({})</h3>\n<ul>", escape(&leader.language)).unwrap();
+        for sensitivity in &evaluation.sensitivity {
+            writeln!(out, "<li>{}</li>", escape(&report::describe_sensitivity(sensitivity, 0))).unwrap();
+        }
+        writeln!(out, "</ul>").unwrap();
+    }
+    writeln!(out, "</section>").unwrap();
+}
+
+fn render_system(out: &mut String, system: &SystemEvaluation) {
+    let best = &system.best;
+    writeln!(out, "<section>\n<h2>{} — portfolio</h2>", escape(&system.system)).unwrap();
+    writeln!(out, "<table>\n<tr><th>Component</th><th>Language</th><th>Score</th></tr>").unwrap();
+    for choice in &best.choices {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>",
+            escape(&choice.component),
+            escape(&choice.language),
+            report::format_score(choice.score)
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>\n<table>").unwrap();
+    for (label, value, detail) in report::assignment_breakdown(best) {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{:+.2}</td><td>{}</td></tr>",
+            label,
+            value,
+            escape(&detail)
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "<tr><th>Portfolio score</th><th class=\"num\">{}</th><th></th></tr>\n</table>\n</section>",
+        report::format_score(best.portfolio_score)
+    )
+    .unwrap();
+    for evaluation in &system.components {
+        render_evaluation(out, evaluation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::criteria::Weights;
+    use crate::LanguageScore;
+
+    fn score(language: &str, scores: [f64; 6]) -> LanguageScore {
+        LanguageScore {
+            language: language.to_string(),
+            total_score: Weights::default().weighted_average(&scores),
+            scores,
+            explanations: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_radar_geometry() {
+        // 最初の軸は真上、満点で半径いっぱい
+        let (x, y) = point(0, 4, 1.0);
+        assert!((x - SIZE / 2.0).abs() < 1e-9 && (y - (SIZE / 2.0 - RADIUS)).abs() < 1e-9);
+        // 4本なら2本目は右
+        let (x, y) = point(1, 4, 0.5);
+        assert!((x - (SIZE / 2.0 + RADIUS / 2.0)).abs() < 1e-9 && (y - SIZE / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_is_self_contained_and_escaped() {
+        let evaluation = Evaluation::new(
+            "<script>alert(1)</script>".to_string(),
+            Weights::default(),
+            vec![score("C++", [9.5, 2.0, 5.0, 3.0, 7.0, 6.0]), score("A&B", [f64::NAN; 6])],
+        );
+        let html = render(&[Report::Project(evaluation)]);
+        assert!(html.contains("<svg"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("A&amp;B"));
+        // 外部のリソースを参照しない
+        assert!(!html.contains("src=") && !html.contains("href="));
+        // 重み付きの4項目が軸になる
+        assert_eq!(html.matches("<line ").count(), 4);
+    }
+}
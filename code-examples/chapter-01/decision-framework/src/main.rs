@@ -1,221 +1,178 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+mod criteria;
+mod html;
+mod portfolio;
+mod report;
+mod rules;
+mod scenario;
+mod sensitivity;
+mod server;
+mod store;
+
+use criteria::{Criterion, Weights};
+use report::{Evaluation, OutputFormat, Report};
+use rules::{Explanation, LanguageRules, RuleSet};
+use scenario::{Input, Scenario};
+use store::ScenarioStore;
+
+const USAGE: &str = "Usage: decision-framework [--rules <FILE>] [--format <FORMAT>] [REQUIREMENTS...]
+       decision-framework [--rules <FILE>] --serve <ADDR> [--store <DIR>]
+
+  --rules <FILE>      load language rules from a TOML or JSON file (default: built-in rules)
+  --format <FORMAT>   output format: table, json, markdown or html (default: table)
+  REQUIREMENTS        project requirement files (.json, .yaml, .yml or .toml);
+                      files with `components` describe a multi-component system.
+                      Without any, the built-in demo scenarios are evaluated
+  --serve <ADDR>      run the web UI and JSON API on ADDR, e.g. 127.0.0.1:3000
+  --store <DIR>       directory for shared scenario links (default: saved-scenarios).
+                      Saving is unauthenticated and unlimited; serve on trusted networks only";
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct ProjectRequirements {
     performance_critical: bool,
     memory_constraints: bool,
+    #[serde(serialize_with = "serialize_sorted")]
     team_experience: HashMap<String, i32>,  // 言語 → 経験年数
     development_timeline: i32,              // 月数
     maintenance_period: i32,                // 年数
     concurrent_users: i32,
     data_volume_gb: i32,
+    #[serde(default)]
+    weights: Weights,                       // 評価項目ごとの重み
+}
+
+// `HashMap` の反復順はインスタンスごとに変わるため、キーでソートして書き出す
+// （共有リンクのIDは書き出したJSONのハッシュなので、同じ内容なら同じバイト列にする）
+fn serialize_sorted<S: serde::Serializer>(map: &HashMap<String, i32>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
 
 #[derive(Debug)]
 struct LanguageScore {
     language: String,
-    performance_score: f64,
-    development_speed_score: f64,
-    maintenance_score: f64,
-    risk_score: f64,
+    scores: [f64; Criterion::ALL.len()],    // `Criterion::index()` の順
+    explanations: [Explanation; Criterion::ALL.len()],
     total_score: f64,
 }
 
-struct TechDecisionFramework;
+impl LanguageScore {
+    fn score(&self, criterion: Criterion) -> f64 {
+        self.scores[criterion.index()]
+    }
 
-impl TechDecisionFramework {
-    fn evaluate_rust(req: &ProjectRequirements) -> LanguageScore {
-        let performance_score = if req.performance_critical {
-            9.0
-        } else {
-            7.0
-        };
-        
-        let development_speed_score = match req.team_experience.get("rust").unwrap_or(&0) {
-            0 => 3.0,  // 学習コストが高い
-            1..=2 => 6.0,
-            _ => 8.0,
-        };
-        
-        let maintenance_score = if req.maintenance_period > 5 {
-            9.0  // 型安全性が長期的に有利
-        } else {
-            7.0
-        };
-        
-        let risk_score = if req.development_timeline < 6 {
-            4.0  // 短期開発にはリスク
-        } else {
-            8.0
-        };
-        
-        let total_score = (performance_score + development_speed_score + 
-                          maintenance_score + risk_score) / 4.0;
-        
-        LanguageScore {
-            language: "Rust".to_string(),
-            performance_score,
-            development_speed_score,
-            maintenance_score,
-            risk_score,
-            total_score,
-        }
+    fn explanation(&self, criterion: Criterion) -> &Explanation {
+        &self.explanations[criterion.index()]
     }
-    
-    fn evaluate_java(req: &ProjectRequirements) -> LanguageScore {
-        let performance_score = if req.performance_critical && req.concurrent_users > 10000 {
-            6.0  // GCがボトルネックになる可能性
-        } else {
-            8.0
-        };
-        
-        let development_speed_score = match req.team_experience.get("java").unwrap_or(&0) {
-            0 => 5.0,
-            1..=2 => 8.0,
-            _ => 9.0,
-        };
-        
-        let maintenance_score = 8.0;  // エコシステムが豊富
-        
-        let risk_score = 9.0;  // 安定した技術
-        
-        let total_score = (performance_score + development_speed_score + 
-                          maintenance_score + risk_score) / 4.0;
-        
-        LanguageScore {
-            language: "Java".to_string(),
-            performance_score,
-            development_speed_score,
-            maintenance_score,
-            risk_score,
-            total_score,
-        }
+}
+
+/// 設定ファイルのルールに従って言語を評価する
+struct TechDecisionFramework {
+    rules: RuleSet,
+}
+
+impl TechDecisionFramework {
+    fn new(rules: RuleSet) -> Self {
+        TechDecisionFramework { rules }
     }
     
-    fn evaluate_python(req: &ProjectRequirements) -> LanguageScore {
-        let performance_score = if req.performance_critical {
-            4.0  // 性能がボトルネック
-        } else {
-            7.0
-        };
-        
-        let development_speed_score = 9.0;  // 開発速度は高速
-        
-        let maintenance_score = if req.data_volume_gb > 100 {
-            5.0  // 大規模データで問題が出やすい
-        } else {
-            7.0
-        };
-        
-        let risk_score = 8.0;
+    fn evaluate(language: &LanguageRules, req: &ProjectRequirements) -> LanguageScore {
+        let outcomes = Criterion::ALL.map(|criterion| {
+            language.criterion(criterion).evaluate(req, &language.experience_key)
+        });
+        let scores = outcomes.each_ref().map(|outcome| outcome.score);
         
-        let total_score = (performance_score + development_speed_score + 
-                          maintenance_score + risk_score) / 4.0;
+        // 項目ごとのスコアを、プロジェクトの重みで平均する
+        let total_score = req.weights.weighted_average(&scores);
         
         LanguageScore {
-            language: "Python".to_string(),
-            performance_score,
-            development_speed_score,
-            maintenance_score,
-            risk_score,
+            language: language.name.clone(),
+            scores,
+            explanations: outcomes.map(|outcome| outcome.explanation),
             total_score,
         }
     }
     
-    fn evaluate_cpp(req: &ProjectRequirements) -> LanguageScore {
-        let performance_score = 9.5;  // 非常に高性能
-        
-        let development_speed_score = match req.team_experience.get("cpp").unwrap_or(&0) {
-            0 => 2.0,  // 学習曲線が急
-            1..=2 => 5.0,
-            _ => 7.0,
-        };
+    pub fn recommend_language(&self, req: &ProjectRequirements) -> Result<Vec<LanguageScore>, String> {
+        req.weights.validate()?;
+        let mut scores: Vec<LanguageScore> = self
+            .rules
+            .languages
+            .iter()
+            .map(|language| Self::evaluate(language, req))
+            .collect();
         
-        let maintenance_score = if req.maintenance_period > 5 {
-            5.0  // 複雑さが長期的にコスト
-        } else {
-            7.0
-        };
-        
-        let risk_score = if req.team_experience.get("cpp").unwrap_or(&0) < &3 {
-            3.0  // メモリ管理のリスク
-        } else {
-            7.0
-        };
-        
-        let total_score = (performance_score + development_speed_score + 
-                          maintenance_score + risk_score) / 4.0;
-        
-        LanguageScore {
-            language: "C++".to_string(),
-            performance_score,
-            development_speed_score,
-            maintenance_score,
-            risk_score,
-            total_score,
-        }
+        // 安定ソートなので、同点の言語はルールファイルの順に並ぶ
+        scores.sort_by(|a, b| report::compare_totals(a.total_score, b.total_score));
+        Ok(scores)
     }
-    
-    fn evaluate_go(req: &ProjectRequirements) -> LanguageScore {
-        let performance_score = if req.performance_critical && req.memory_constraints {
-            6.5  // GCがある
-        } else {
-            8.0
-        };
-        
-        let development_speed_score = match req.team_experience.get("go").unwrap_or(&0) {
-            0 => 6.0,  // 比較的学習しやすい
-            1..=2 => 8.0,
-            _ => 9.0,
-        };
-        
-        let maintenance_score = 8.0;  // シンプルな言語設計
-        
-        let risk_score = 8.0;
-        
-        let total_score = (performance_score + development_speed_score + 
-                          maintenance_score + risk_score) / 4.0;
-        
-        LanguageScore {
-            language: "Go".to_string(),
-            performance_score,
-            development_speed_score,
-            maintenance_score,
-            risk_score,
-            total_score,
+}
+
+struct Options {
+    rules: RuleSet,
+    format: OutputFormat,
+    files: Vec<PathBuf>,
+    serve: Option<SocketAddr>,
+    store: PathBuf,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        rules: RuleSet::builtin(),
+        format: OutputFormat::Table,
+        files: Vec::new(),
+        serve: None,
+        store: PathBuf::from("saved-scenarios"),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let path = args.next().ok_or(USAGE)?;
+                options.rules = RuleSet::load(Path::new(&path)).map_err(|e| e.to_string())?;
+            }
+            "--format" => {
+                let name = args.next().ok_or(USAGE)?;
+                options.format = OutputFormat::parse(&name)
+                    .ok_or_else(|| format!("unknown format `{}` (expected table, json, markdown or html)", name))?;
+            }
+            "--serve" => {
+                let addr = args.next().ok_or(USAGE)?;
+                options.serve = Some(addr.parse().map_err(|_| format!("invalid address `{}`", addr))?);
+            }
+            "--store" => options.store = PathBuf::from(args.next().ok_or(USAGE)?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            flag if flag.starts_with('-') => return Err(USAGE.to_string()),
+            path => options.files.push(PathBuf::from(path)),
         }
     }
-    
-    pub fn recommend_language(req: &ProjectRequirements) -> Vec<LanguageScore> {
-        let mut scores = vec![
-            Self::evaluate_rust(req),
-            Self::evaluate_java(req),
-            Self::evaluate_python(req),
-            Self::evaluate_cpp(req),
-            Self::evaluate_go(req),
-        ];
-        
-        scores.sort_by(|a, b| b.total_score.partial_cmp(&a.total_score).unwrap());
-        scores
+    if options.serve.is_some() && !options.files.is_empty() {
+        return Err("requirement files cannot be combined with --serve".to_string());
     }
+    Ok(options)
 }
 
-fn print_recommendations(scenario: &str, recommendations: &Vec<LanguageScore>) {
-    println!("\n{}", "=".repeat(60));
-    println!("Scenario: {}", scenario);
-    println!("{}", "=".repeat(60));
-    
-    for (i, score) in recommendations.iter().enumerate() {
-        println!("{}. {} (Score: {:.1})", i+1, score.language, score.total_score);
-        println!("   Performance: {:.1}, Dev Speed: {:.1}, Maintenance: {:.1}, Risk: {:.1}",
-            score.performance_score, score.development_speed_score, 
-            score.maintenance_score, score.risk_score);
+fn serve(addr: SocketAddr, framework: TechDecisionFramework, store: &Path) {
+    let result = ScenarioStore::open(store).and_then(|store| {
+        let state = server::AppState { framework, store };
+        tokio::runtime::Runtime::new()?.block_on(server::run(addr, state))
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
 
-fn main() {
-    println!("=== Tech Decision Framework Demo ===");
+// 要件ファイルが指定されなかったときのデモ用シナリオ
+fn demo_scenarios() -> Vec<Scenario> {
+    let mut scenarios = Vec::new();
     
     // シナリオ1: 高性能Webサービス
     let high_performance_web = ProjectRequirements {
@@ -234,10 +191,10 @@ fn main() {
         maintenance_period: 10,
         concurrent_users: 50000,
         data_volume_gb: 500,
+        weights: Weights::default(),
     };
     
-    let recommendations = TechDecisionFramework::recommend_language(&high_performance_web);
-    print_recommendations("High-performance web service", &recommendations);
+    scenarios.push(Scenario { name: "High-performance web service".to_string(), requirements: high_performance_web });
     
     // シナリオ2: プロトタイプ開発
     let prototype_project = ProjectRequirements {
@@ -256,10 +213,10 @@ fn main() {
         maintenance_period: 1,
         concurrent_users: 100,
         data_volume_gb: 10,
+        weights: Weights::default(),
     };
     
-    let recommendations = TechDecisionFramework::recommend_language(&prototype_project);
-    print_recommendations("Rapid prototype development", &recommendations);
+    scenarios.push(Scenario { name: "Rapid prototype development".to_string(), requirements: prototype_project });
     
     // シナリオ3: 組み込みシステム
     let embedded_system = ProjectRequirements {
@@ -278,8 +235,92 @@ fn main() {
         maintenance_period: 15,
         concurrent_users: 0,
         data_volume_gb: 1,
+        weights: Weights::default(),
     };
     
-    let recommendations = TechDecisionFramework::recommend_language(&embedded_system);
-    print_recommendations("Embedded system development", &recommendations);
-}
\ No newline at end of file
+    scenarios.push(Scenario { name: "Embedded system development".to_string(), requirements: embedded_system });
+    
+    // シナリオ4: 長期運用の業務システム（採用とエコシステムを重視）
+    let enterprise_backend = ProjectRequirements {
+        performance_critical: false,
+        memory_constraints: false,
+        team_experience: {
+            let mut exp = HashMap::new();
+            exp.insert("java".to_string(), 2);
+            exp.insert("go".to_string(), 1);
+            exp.insert("rust".to_string(), 1);
+            exp.insert("python".to_string(), 2);
+            exp
+        },
+        development_timeline: 12,
+        maintenance_period: 10,
+        concurrent_users: 5000,
+        data_volume_gb: 200,
+        weights: Weights {
+            risk: 2.0,
+            ecosystem_maturity: 1.5,
+            hiring_pool: 1.5,
+            ..Weights::default()
+        },
+    };
+    
+    scenarios.push(Scenario { name: "Long-lived enterprise backend".to_string(), requirements: enterprise_backend });
+    
+    scenarios
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let framework = TechDecisionFramework::new(options.rules);
+    
+    if let Some(addr) = options.serve {
+        serve(addr, framework, &options.store);
+        return;
+    }
+    
+    let demo = options.files.is_empty();
+    let inputs = if demo {
+        demo_scenarios().into_iter().map(Input::Project).collect()
+    } else {
+        // 1つでも読めないファイルがあれば、何も出力せずに終わる
+        match options.files.iter().map(|path| scenario::load(path)).collect::<Result<Vec<_>, _>>() {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+    
+    let mut reports = Vec::new();
+    for input in inputs {
+        let result = match input {
+            Input::Project(scenario) => framework
+                .recommend_language(&scenario.requirements)
+                .map(|ranking| Report::Project(Evaluation::new(scenario.name.clone(), scenario.requirements.weights, ranking)))
+                .map_err(|e| format!("{}: {}", scenario.name, e)),
+            Input::System(system) => framework
+                .evaluate_system(&system.name, &system.description)
+                .map(Report::System)
+                .map_err(|e| format!("{}: {}", system.name, e)),
+        };
+        match result {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    
+    if demo && options.format == OutputFormat::Table {
+        println!("=== Tech Decision Framework Demo ===");
+    }
+    print!("{}", report::render(options.format, &reports));
+}
@@ -0,0 +1,429 @@
+// 複数のコンポーネントからなるシステムの評価
+//
+// コンポーネント（APIゲートウェイ、バッチ、CLIなど）ごとに言語を評価したうえで、
+// 組み合わせ全体（ポートフォリオ）を採点する。コンポーネントごとに最適な言語を
+// 選ぶと言語がばらばらになりがちなので、次のものを減点する。
+//
+// - 言語の種類が増えること（ビルド・運用・レビューの負担）
+// - チームの経験が足りない言語を使うこと（`team_experience` でカバーできない）
+// - 同じプロセス内でつながる（`links`）コンポーネントの言語が違うこと（FFIの境界）
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::criteria::Weights;
+use crate::report::{self, Evaluation};
+use crate::{ProjectRequirements, TechDecisionFramework};
+
+/// この年数以上の経験があれば、その言語はチームで十分カバーできているとみなす
+const COVERED_YEARS: f64 = 3.0;
+
+/// 試す組み合わせ（候補の数^コンポーネント数）の上限。全探索なので、これを超えると終わらない
+pub const MAX_COMBINATIONS: u64 = 1_000_000;
+
+/// 減点の大きさ（スコアと同じ0〜10の尺度）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PortfolioPolicy {
+    /// 2つ目以降の言語1つあたり
+    pub language_penalty: f64,
+    /// 経験0年の言語1つあたり（経験があれば `COVERED_YEARS` まで比例して減る）
+    pub uncovered_penalty: f64,
+    /// 言語の違うコンポーネント間の `links` 1本あたり
+    pub ffi_penalty: f64,
+    /// 組み合わせを探すときの、コンポーネントごとの候補の数（上位から）
+    pub candidates: usize,
+}
+
+impl Default for PortfolioPolicy {
+    fn default() -> Self {
+        PortfolioPolicy {
+            language_penalty: 0.5,
+            uncovered_penalty: 1.0,
+            ffi_penalty: 0.75,
+            candidates: 3,
+        }
+    }
+}
+
+/// システムを構成する1つのコンポーネント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Component {
+    pub name: String,
+    pub performance_critical: bool,
+    pub memory_constraints: bool,
+    pub development_timeline: i32,          // 月数
+    pub maintenance_period: i32,            // 年数
+    pub concurrent_users: i32,
+    pub data_volume_gb: i32,
+    /// 省略時はシステム全体の `weights`
+    pub weights: Option<Weights>,
+    /// 同じプロセス内で直接呼び出すコンポーネント（言語が違えばFFIになる）
+    #[serde(default)]
+    pub links: Vec<String>,
+}
+
+/// 複数のコンポーネントからなるシステムの記述
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SystemDescription {
+    pub team_experience: HashMap<String, i32>,  // 言語 → 経験年数（全コンポーネント共通）
+    #[serde(default)]
+    pub weights: Weights,
+    #[serde(default)]
+    pub portfolio: PortfolioPolicy,
+    pub components: Vec<Component>,
+}
+
+impl SystemDescription {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.components.is_empty() {
+            return Err("no components are defined".to_string());
+        }
+        self.weights.validate()?;
+        let policy = &self.portfolio;
+        for (name, penalty) in [
+            ("language_penalty", policy.language_penalty),
+            ("uncovered_penalty", policy.uncovered_penalty),
+            ("ffi_penalty", policy.ffi_penalty),
+        ] {
+            if !penalty.is_finite() || penalty < 0.0 {
+                return Err(format!("`portfolio.{}` must be a non-negative number, got {}", name, penalty));
+            }
+        }
+        if policy.candidates == 0 {
+            return Err("`portfolio.candidates` must be at least 1".to_string());
+        }
+        let combinations = u32::try_from(self.components.len())
+            .ok()
+            .and_then(|components| (policy.candidates as u64).checked_pow(components))
+            .filter(|&n| n <= MAX_COMBINATIONS);
+        if combinations.is_none() {
+            return Err(format!(
+                "{} components with `portfolio.candidates` = {} exceed the search limit of {} combinations; \
+                 lower `portfolio.candidates` or split the system",
+                self.components.len(),
+                policy.candidates,
+                MAX_COMBINATIONS
+            ));
+        }
+
+        let mut names = BTreeSet::new();
+        for component in &self.components {
+            if component.name.trim().is_empty() {
+                return Err("component name is empty".to_string());
+            }
+            if !names.insert(component.name.as_str()) {
+                return Err(format!("component `{}` is defined more than once", component.name));
+            }
+            if let Some(weights) = &component.weights {
+                weights.validate().map_err(|e| format!("component `{}`: {}", component.name, e))?;
+            }
+        }
+        for component in &self.components {
+            for link in &component.links {
+                if link == &component.name {
+                    return Err(format!("component `{}` links to itself", component.name));
+                }
+                if !names.contains(link.as_str()) {
+                    return Err(format!("component `{}` links to unknown component `{}`", component.name, link));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn requirements(&self, component: &Component) -> ProjectRequirements {
+        ProjectRequirements {
+            performance_critical: component.performance_critical,
+            memory_constraints: component.memory_constraints,
+            team_experience: self.team_experience.clone(),
+            development_timeline: component.development_timeline,
+            maintenance_period: component.maintenance_period,
+            concurrent_users: component.concurrent_users,
+            data_volume_gb: component.data_volume_gb,
+            weights: component.weights.unwrap_or(self.weights),
+        }
+    }
+
+    // `links` を向きのない辺にして、重複を除く（コンポーネントの番号の組）
+    fn boundaries(&self) -> Vec<(usize, usize)> {
+        let index = |name: &str| self.components.iter().position(|c| c.name == name);
+        let mut edges = BTreeSet::new();
+        for (i, component) in self.components.iter().enumerate() {
+            for j in component.links.iter().filter_map(|link| index(link)) {
+                edges.insert((i.min(j), i.max(j)));
+            }
+        }
+        edges.into_iter().collect()
+    }
+}
+
+/// 言語ごとの、チームの経験によるカバー率
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+    pub language: String,
+    pub years: i32,
+    /// 0（経験なし）〜 1（`COVERED_YEARS` 以上）
+    pub coverage: f64,
+}
+
+/// 言語の違うコンポーネント間のつながり
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FfiBoundary {
+    pub from: String,
+    pub to: String,
+}
+
+/// 1つのコンポーネントに割り当てた言語
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Choice {
+    pub component: String,
+    pub language: String,
+    /// そのコンポーネントでの言語の総合スコア
+    pub score: f64,
+}
+
+/// コンポーネントへの言語の割り当てと、その採点
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Assignment {
+    /// コンポーネントの順
+    pub choices: Vec<Choice>,
+    /// 各コンポーネントの総合スコアの平均
+    pub component_score: f64,
+    pub languages_penalty: f64,
+    pub coverage_penalty: f64,
+    pub ffi_penalty: f64,
+    pub coverage: Vec<Coverage>,
+    pub ffi_boundaries: Vec<FfiBoundary>,
+    pub portfolio_score: f64,
+}
+
+/// システム全体の評価結果
+pub struct SystemEvaluation {
+    pub system: String,
+    /// コンポーネントごとの評価（シナリオ名は `<システム> / <コンポーネント>`）
+    pub components: Vec<Evaluation>,
+    /// ポートフォリオのスコアが最も高い割り当て
+    pub best: Assignment,
+    /// 各コンポーネントの1位をそのまま使った割り当て（比較用）
+    pub independent: Assignment,
+}
+
+// 割り当ての採点に必要なものをまとめておく
+struct Scorer<'a> {
+    description: &'a SystemDescription,
+    components: &'a [Evaluation],
+    boundaries: Vec<(usize, usize)>,
+    // 言語名 → `team_experience` のキー
+    experience_keys: HashMap<&'a str, &'a str>,
+}
+
+impl Scorer<'_> {
+    // `choice[i]` はコンポーネント i のランキングの何番目を使うか
+    fn score(&self, choice: &[usize]) -> Assignment {
+        let policy = &self.description.portfolio;
+        let picked: Vec<&crate::LanguageScore> = choice
+            .iter()
+            .zip(self.components)
+            .map(|(&rank, evaluation)| &evaluation.ranking[rank])
+            .collect();
+        let component_score = picked.iter().map(|score| score.total_score).sum::<f64>() / picked.len() as f64;
+
+        // 最初に使われた順に並べる
+        let mut distinct: Vec<&str> = Vec::new();
+        for score in &picked {
+            if !distinct.contains(&score.language.as_str()) {
+                distinct.push(&score.language);
+            }
+        }
+        let coverage: Vec<Coverage> = distinct
+            .iter()
+            .map(|&language| {
+                let key = self.experience_keys.get(language).copied().unwrap_or(language);
+                let years = *self.description.team_experience.get(key).unwrap_or(&0);
+                Coverage {
+                    language: language.to_string(),
+                    years,
+                    coverage: (years.max(0) as f64 / COVERED_YEARS).min(1.0),
+                }
+            })
+            .collect();
+        let ffi_boundaries: Vec<FfiBoundary> = self
+            .boundaries
+            .iter()
+            .filter(|&&(i, j)| picked[i].language != picked[j].language)
+            .map(|&(i, j)| FfiBoundary {
+                from: self.description.components[i].name.clone(),
+                to: self.description.components[j].name.clone(),
+            })
+            .collect();
+
+        let languages_penalty = policy.language_penalty * (distinct.len() - 1) as f64;
+        let coverage_penalty = policy.uncovered_penalty * coverage.iter().map(|c| 1.0 - c.coverage).sum::<f64>();
+        let ffi_penalty = policy.ffi_penalty * ffi_boundaries.len() as f64;
+
+        Assignment {
+            choices: self
+                .description
+                .components
+                .iter()
+                .zip(&picked)
+                .map(|(component, score)| Choice {
+                    component: component.name.clone(),
+                    language: score.language.clone(),
+                    score: score.total_score,
+                })
+                .collect(),
+            component_score,
+            languages_penalty,
+            coverage_penalty,
+            ffi_penalty,
+            coverage,
+            ffi_boundaries,
+            portfolio_score: component_score - languages_penalty - coverage_penalty - ffi_penalty,
+        }
+    }
+}
+
+impl TechDecisionFramework {
+    /// コンポーネントごとに評価し、ポートフォリオとして最も良い言語の組み合わせを探す
+    pub fn evaluate_system(&self, system: &str, description: &SystemDescription) -> Result<SystemEvaluation, String> {
+        description.validate()?;
+        let components = description
+            .components
+            .iter()
+            .map(|component| {
+                let req = description.requirements(component);
+                let ranking = self.recommend_language(&req)?;
+                Ok(Evaluation::new(format!("{} / {}", system, component.name), req.weights, ranking))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let scorer = Scorer {
+            description,
+            components: &components,
+            boundaries: description.boundaries(),
+            experience_keys: self
+                .rules
+                .languages
+                .iter()
+                .map(|language| (language.name.as_str(), language.experience_key.as_str()))
+                .collect(),
+        };
+
+        // 候補は各コンポーネントの上位（NaN は除く）。候補の数^コンポーネント数の組み合わせを全て試す
+        let limits: Vec<usize> = components
+            .iter()
+            .map(|evaluation| {
+                let ranked = evaluation.ranking.iter().filter(|s| !s.total_score.is_nan()).count();
+                ranked.min(description.portfolio.candidates)
+            })
+            .collect();
+        if let Some(i) = limits.iter().position(|&limit| limit == 0) {
+            return Err(format!("component `{}` has no scorable language", description.components[i].name));
+        }
+
+        let mut choice = vec![0; components.len()];
+        let independent = scorer.score(&choice);
+        let mut best = independent.clone();
+        // 桁ごとに候補を数え上げる（オドメーター）。同点なら先に見つかった方（上位の候補が多い方）を残す
+        while next_choice(&mut choice, &limits) {
+            let assignment = scorer.score(&choice);
+            if report::compare_totals(assignment.portfolio_score, best.portfolio_score).is_lt() {
+                best = assignment;
+            }
+        }
+
+        Ok(SystemEvaluation {
+            system: system.to_string(),
+            components,
+            best,
+            independent,
+        })
+    }
+}
+
+// 次の組み合わせに進める。全て試し終えたら `false`
+fn next_choice(choice: &mut [usize], limits: &[usize]) -> bool {
+    for (digit, &limit) in choice.iter_mut().zip(limits).rev() {
+        *digit += 1;
+        if *digit < limit {
+            return true;
+        }
+        *digit = 0;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    const SYSTEM: &str = include_str!("../scenarios/platform.yaml");
+
+    fn description() -> SystemDescription {
+        serde_yaml::from_str(SYSTEM).unwrap()
+    }
+
+    #[test]
+    fn test_next_choice_visits_every_combination() {
+        let limits = [2, 3];
+        let mut choice = vec![0, 0];
+        let mut seen = vec![choice.clone()];
+        while next_choice(&mut choice, &limits) {
+            seen.push(choice.clone());
+        }
+        assert_eq!(seen.len(), 6);
+        assert_eq!(seen.last(), Some(&vec![1, 2]));
+    }
+
+    #[test]
+    fn test_portfolio_penalizes_languages_and_ffi() {
+        let framework = TechDecisionFramework::new(RuleSet::builtin());
+        let result = framework.evaluate_system("platform", &description()).unwrap();
+        assert_eq!(result.components.len(), 4);
+
+        let best = &result.best;
+        assert!(best.portfolio_score >= result.independent.portfolio_score);
+        let expected = best.component_score - best.languages_penalty - best.coverage_penalty - best.ffi_penalty;
+        assert!((best.portfolio_score - expected).abs() < 1e-9);
+
+        // 言語を1つに揃えれば、言語数とFFIの減点はなくなる
+        let mut same = description();
+        same.portfolio.candidates = 5;
+        same.portfolio.language_penalty = 100.0;
+        let result = framework.evaluate_system("platform", &same).unwrap();
+        let languages: BTreeSet<&str> = result.best.choices.iter().map(|c| c.language.as_str()).collect();
+        assert_eq!(languages.len(), 1);
+        assert_eq!(result.best.ffi_penalty, 0.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut broken = description();
+        broken.components[0].links.push("nowhere".to_string());
+        assert!(broken.validate().unwrap_err().contains("unknown component `nowhere`"));
+
+        let mut duplicated = description();
+        duplicated.components[1].name = duplicated.components[0].name.clone();
+        assert!(duplicated.validate().unwrap_err().contains("more than once"));
+
+        // 5^20 通りは試さずに断る
+        let mut large = description();
+        let template = large.components[0].clone();
+        large.components = (0..20)
+            .map(|i| Component {
+                name: format!("c{}", i),
+                links: Vec::new(),
+                ..template.clone()
+            })
+            .collect();
+        large.portfolio.candidates = 5;
+        assert!(large.validate().unwrap_err().contains("search limit of 1000000 combinations"));
+        large.portfolio.candidates = 1;
+        assert!(large.validate().is_ok());
+    }
+}
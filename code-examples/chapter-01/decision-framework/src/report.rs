@@ -0,0 +1,458 @@
+// 評価結果の出力（表 / JSON / Markdown / HTML）
+//
+// どの形式でも、各スコアにはそれを決めたルール（`Explanation`）を添える。
+
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use crate::criteria::{Criterion, Weights};
+use crate::html;
+use crate::portfolio::{Assignment, SystemEvaluation};
+use crate::sensitivity::{self, Sensitivity};
+use crate::LanguageScore;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Markdown,
+    /// レーダーチャート付きの1ファイルのHTML（`html.rs`）
+    Html,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<OutputFormat> {
+        match name {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "html" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// 1つのシナリオの評価結果
+pub struct Evaluation {
+    pub scenario: String,
+    pub weights: Weights,
+    /// 総合スコアの高い順
+    pub ranking: Vec<LanguageScore>,
+    pub sensitivity: Vec<Sensitivity>,
+}
+
+impl Evaluation {
+    pub fn new(scenario: String, weights: Weights, ranking: Vec<LanguageScore>) -> Self {
+        let sensitivity = sensitivity::analyze(&ranking, &weights);
+        Evaluation {
+            scenario,
+            weights,
+            ranking,
+            sensitivity,
+        }
+    }
+
+    // 重みが0の項目は総合スコアに影響しないので、表やMarkdownには出さない
+    fn weighted_criteria(&self) -> Vec<Criterion> {
+        Criterion::ALL
+            .into_iter()
+            .filter(|&c| self.weights.get(c) > 0.0)
+            .collect()
+    }
+}
+
+/// 出力する評価結果の1件
+pub enum Report {
+    Project(Evaluation),
+    /// 複数のコンポーネントからなるシステム（`portfolio.rs`）
+    System(SystemEvaluation),
+}
+
+/// 総合スコアの降順の比較。NaN は比較できないので最下位に回す
+pub fn compare_totals(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+/// 並んだ順位。同点は同じ順位（1, 1, 3, ...）、NaN は順位なし
+pub fn ranks(ranking: &[LanguageScore]) -> Vec<Option<usize>> {
+    ranking
+        .iter()
+        .map(|score| {
+            if score.total_score.is_nan() {
+                return None;
+            }
+            Some(1 + ranking.iter().filter(|other| other.total_score > score.total_score).count())
+        })
+        .collect()
+}
+
+// 他にも同じ順位の言語があるか
+pub fn is_tied(ranks: &[Option<usize>], i: usize) -> bool {
+    ranks[i].is_some() && ranks.iter().enumerate().any(|(j, rank)| j != i && *rank == ranks[i])
+}
+
+pub fn format_score(score: f64) -> String {
+    if score.is_nan() {
+        "n/a".to_string()
+    } else {
+        format!("{:.1}", score)
+    }
+}
+
+pub fn render(format: OutputFormat, reports: &[Report]) -> String {
+    match format {
+        OutputFormat::Table => reports
+            .iter()
+            .map(|report| match report {
+                Report::Project(evaluation) => render_table(evaluation),
+                Report::System(system) => {
+                    let components: String = system.components.iter().map(render_table).collect();
+                    components + &render_portfolio_table(system)
+                }
+            })
+            .collect(),
+        OutputFormat::Json => {
+            let value: Vec<Value> = reports
+                .iter()
+                .map(|report| match report {
+                    Report::Project(evaluation) => evaluation_json(evaluation),
+                    Report::System(system) => system_json(system),
+                })
+                .collect();
+            // `Value` の直列化は失敗しない
+            serde_json::to_string_pretty(&value).unwrap() + "\n"
+        }
+        OutputFormat::Markdown => reports
+            .iter()
+            .map(|report| match report {
+                Report::Project(evaluation) => render_markdown(evaluation),
+                Report::System(system) => render_system_markdown(system),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Html => html::render(reports),
+    }
+}
+
+/// ポートフォリオのスコアの内訳（項目名、点数、詳細）
+pub fn assignment_breakdown(assignment: &Assignment) -> Vec<(&'static str, f64, String)> {
+    let languages: Vec<&str> = assignment.coverage.iter().map(|c| c.language.as_str()).collect();
+    let uncovered: Vec<String> = assignment
+        .coverage
+        .iter()
+        .filter(|c| c.coverage < 1.0)
+        .map(|c| format!("{}: {} years", c.language, c.years))
+        .collect();
+    let boundaries: Vec<String> = assignment
+        .ffi_boundaries
+        .iter()
+        .map(|b| format!("{} <-> {}", b.from, b.to))
+        .collect();
+    vec![
+        ("Component average", assignment.component_score, String::new()),
+        // `-0.0` と表示されないよう、0から引く
+        ("Languages", 0.0 - assignment.languages_penalty, languages.join(", ")),
+        ("Team coverage", 0.0 - assignment.coverage_penalty, uncovered.join(", ")),
+        ("FFI boundaries", 0.0 - assignment.ffi_penalty, boundaries.join(", ")),
+    ]
+}
+
+fn describe_choices(assignment: &Assignment) -> String {
+    let choices: Vec<String> = assignment
+        .choices
+        .iter()
+        .map(|choice| format!("{}={}", choice.component, choice.language))
+        .collect();
+    choices.join(", ")
+}
+
+// ---- 表 ----
+
+fn render_table(evaluation: &Evaluation) -> String {
+    let mut out = String::new();
+    let ranks = ranks(&evaluation.ranking);
+    let criteria = evaluation.weighted_criteria();
+
+    writeln!(out, "\n{}", "=".repeat(60)).unwrap();
+    writeln!(out, "Scenario: {}", evaluation.scenario).unwrap();
+    writeln!(out, "{}", "=".repeat(60)).unwrap();
+
+    for (i, score) in evaluation.ranking.iter().enumerate() {
+        let rank = ranks[i].map_or("-".to_string(), |rank| rank.to_string());
+        let tied = if is_tied(&ranks, i) { ", tied" } else { "" };
+        writeln!(out, "{}. {} (Score: {}{})", rank, score.language, format_score(score.total_score), tied).unwrap();
+        for &criterion in &criteria {
+            writeln!(
+                out,
+                "   {:<12} {:>4}  {}",
+                criterion.label(),
+                format_score(score.score(criterion)),
+                score.explanation(criterion)
+            )
+            .unwrap();
+        }
+    }
+
+    if let Some(leader) = evaluation.ranking.first() {
+        writeln!(out, "\n   What would change the top choice ({})?", leader.language).unwrap();
+        for sensitivity in &evaluation.sensitivity {
+            writeln!(out, "   - {}", describe_sensitivity(sensitivity, 18)).unwrap();
+        }
+    }
+    out
+}
+
+// `width` は項目名を揃える幅
+pub fn describe_sensitivity(sensitivity: &Sensitivity, width: usize) -> String {
+    let name = sensitivity.criterion.name();
+    let current = sensitivity.current_weight;
+    match &sensitivity.flip {
+        // 同点のときは、どちらに動かしても順位が決まる
+        Some(flip) if flip.delta == 0.0 => {
+            format!("{:<width$} weight {:.2}: already tied with {}", name, current, flip.new_leader)
+        }
+        Some(flip) => format!(
+            "{:<width$} weight {:.2} -> {:.2} ({} by {:.2}) makes {} the top choice",
+            name,
+            current,
+            flip.weight,
+            if flip.delta > 0.0 { "raise" } else { "lower" },
+            flip.delta.abs(),
+            flip.new_leader
+        ),
+        None => format!("{:<width$} weight {:.2}: no change flips the recommendation", name, current),
+    }
+}
+
+// ---- JSON ----
+
+/// 1つのシナリオの評価結果のJSON（`--format json` とWeb APIで共通）
+pub fn evaluation_json(evaluation: &Evaluation) -> Value {
+    let ranks = ranks(&evaluation.ranking);
+    let ranking: Vec<Value> = evaluation
+        .ranking
+        .iter()
+        .enumerate()
+        .map(|(i, score)| {
+            let scores: serde_json::Map<String, Value> = Criterion::ALL
+                .iter()
+                .map(|&c| {
+                    let explanation = score.explanation(c);
+                    let value = json!({
+                        "score": score.score(c),
+                        "weight": evaluation.weights.get(c),
+                        "rule": explanation.rule,
+                        "when": explanation.when,
+                        "reason": explanation.reason,
+                    });
+                    (c.name().to_string(), value)
+                })
+                .collect();
+            json!({
+                "rank": ranks[i],
+                "tied": is_tied(&ranks, i),
+                "language": score.language,
+                // NaN は JSON では null になる
+                "total_score": score.total_score,
+                "scores": scores,
+            })
+        })
+        .collect();
+    json!({
+        "scenario": evaluation.scenario,
+        "weights": evaluation.weights,
+        "ranking": ranking,
+        "sensitivity": evaluation.sensitivity,
+    })
+}
+
+fn render_portfolio_table(system: &SystemEvaluation) -> String {
+    let mut out = String::new();
+    let best = &system.best;
+
+    writeln!(out, "\n{}", "=".repeat(60)).unwrap();
+    writeln!(out, "Portfolio: {}", system.system).unwrap();
+    writeln!(out, "{}", "=".repeat(60)).unwrap();
+    writeln!(out, "Recommended (Portfolio score: {})", format_score(best.portfolio_score)).unwrap();
+    for choice in &best.choices {
+        writeln!(out, "   {:<20} {:<10} {:>4}", choice.component, choice.language, format_score(choice.score)).unwrap();
+    }
+    writeln!(out).unwrap();
+    for (label, value, detail) in assignment_breakdown(best) {
+        let line = format!("   {:<18} {:>+6.2}  {}", label, value, detail);
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    if system.independent != *best {
+        writeln!(
+            out,
+            "\n   Best language per component ({}) scores {} as a portfolio",
+            describe_choices(&system.independent),
+            format_score(system.independent.portfolio_score)
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn system_json(system: &SystemEvaluation) -> Value {
+    json!({
+        "system": system.system,
+        "components": system.components.iter().map(evaluation_json).collect::<Vec<_>>(),
+        "portfolio": {
+            "recommended": system.best,
+            "independent": system.independent,
+        },
+    })
+}
+
+// ---- Markdown ----
+
+fn render_system_markdown(system: &SystemEvaluation) -> String {
+    let mut out = String::new();
+    let best = &system.best;
+
+    writeln!(out, "# {}\n", system.system).unwrap();
+    writeln!(out, "## Portfolio\n").unwrap();
+    writeln!(out, "| Component | Language | Score |\n|:---|:---|---:|").unwrap();
+    for choice in &best.choices {
+        writeln!(
+            out,
+            "| {} | {} | {} |",
+            escape_cell(&choice.component),
+            escape_cell(&choice.language),
+            format_score(choice.score)
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+    for (label, value, detail) in assignment_breakdown(best) {
+        let detail = if detail.is_empty() { String::new() } else { format!(" ({})", detail) };
+        writeln!(out, "- {}: {:+.2}{}", label, value, detail).unwrap();
+    }
+    writeln!(out, "- **Portfolio score: {}**", format_score(best.portfolio_score)).unwrap();
+    if system.independent != *best {
+        writeln!(
+            out,
+            "\nPicking the best language per component ({}) scores {} as a portfolio.",
+            describe_choices(&system.independent),
+            format_score(system.independent.portfolio_score)
+        )
+        .unwrap();
+    }
+    for evaluation in &system.components {
+        writeln!(out, "\n{}", render_markdown(evaluation)).unwrap();
+    }
+    out
+}
+
+// 表のセルの中で `|` は区切りになってしまう
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_markdown(evaluation: &Evaluation) -> String {
+    let mut out = String::new();
+    let ranks = ranks(&evaluation.ranking);
+    let criteria = evaluation.weighted_criteria();
+
+    writeln!(out, "## {}\n", evaluation.scenario).unwrap();
+
+    write!(out, "| Rank | Language | Score |").unwrap();
+    for &criterion in &criteria {
+        write!(out, " {} (×{}) |", criterion.label(), evaluation.weights.get(criterion)).unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out, "|---:|:---|---:|{}", "---:|".repeat(criteria.len())).unwrap();
+    for (i, score) in evaluation.ranking.iter().enumerate() {
+        let rank = match ranks[i] {
+            Some(rank) if is_tied(&ranks, i) => format!("{}=", rank),
+            Some(rank) => rank.to_string(),
+            None => "-".to_string(),
+        };
+        write!(out, "| {} | {} | {} |", rank, escape_cell(&score.language), format_score(score.total_score)).unwrap();
+        for &criterion in &criteria {
+            write!(out, " {} |", format_score(score.score(criterion))).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    writeln!(out, "\n### Why these scores\n").unwrap();
+    for score in &evaluation.ranking {
+        writeln!(out, "- **{}**", score.language).unwrap();
+        for &criterion in &criteria {
+            writeln!(
+                out,
+                "  - {} {}: {}",
+                criterion.label(),
+                format_score(score.score(criterion)),
+                score.explanation(criterion)
+            )
+            .unwrap();
+        }
+    }
+
+    if let Some(leader) = evaluation.ranking.first() {
+        writeln!(out, "\n### What would change the top choice ({})\n", leader.language).unwrap();
+        for sensitivity in &evaluation.sensitivity {
+            writeln!(out, "- {}", describe_sensitivity(sensitivity, 0)).unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(language: &str, total_score: f64) -> LanguageScore {
+        LanguageScore {
+            language: language.to_string(),
+            scores: [total_score; 6],
+            explanations: Default::default(),
+            total_score,
+        }
+    }
+
+    #[test]
+    fn test_nan_sorts_last_and_ties_share_rank() {
+        let mut ranking = vec![score("A", f64::NAN), score("B", 7.0), score("C", 8.0), score("D", 7.0)];
+        ranking.sort_by(|a, b| compare_totals(a.total_score, b.total_score));
+        let order: Vec<&str> = ranking.iter().map(|s| s.language.as_str()).collect();
+        // 同点は元の順序のまま
+        assert_eq!(order, ["C", "B", "D", "A"]);
+
+        let ranks = ranks(&ranking);
+        assert_eq!(ranks, [Some(1), Some(2), Some(2), None]);
+        assert!(!is_tied(&ranks, 0));
+        assert!(is_tied(&ranks, 1));
+        assert!(!is_tied(&ranks, 3));
+    }
+
+    #[test]
+    fn test_json_output() {
+        let evaluation = Evaluation::new(
+            "s".to_string(),
+            Weights::default(),
+            vec![score("A", 8.0), score("B", 8.0), score("C", f64::NAN)],
+        );
+        let value: Value = serde_json::from_str(&render(OutputFormat::Json, &[Report::Project(evaluation)])).unwrap();
+        let ranking = &value[0]["ranking"];
+        assert_eq!(ranking[1]["rank"], 1);
+        assert_eq!(ranking[1]["tied"], true);
+        assert_eq!(ranking[2]["rank"], Value::Null);
+        assert_eq!(ranking[2]["total_score"], Value::Null);
+        assert_eq!(ranking[0]["scores"]["risk"]["rule"], Value::Null);
+    }
+
+    #[test]
+    fn test_markdown_escapes_cells() {
+        let evaluation = Evaluation::new("s".to_string(), Weights::default(), vec![score("A|B", 8.0)]);
+        let markdown = render(OutputFormat::Markdown, &[Report::Project(evaluation)]);
+        assert!(markdown.contains("| 1 | A\\|B | 8.0 |"), "{}", markdown);
+    }
+}
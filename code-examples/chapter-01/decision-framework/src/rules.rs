@@ -0,0 +1,591 @@
+// 言語の評価ルールを設定ファイル（TOML/JSON）から読み込む
+//
+// ルールの書き方は `rules/default.toml` を参照。読み込み時に条件式を解析して
+// 検査し、間違いがあればどのルールが悪いのかを示すエラーにする。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::criteria::Criterion;
+use crate::ProjectRequirements;
+
+/// 組み込みのルール（以前のハードコードされた評価と同じ結果になる）
+pub const DEFAULT_RULES: &str = include_str!("../rules/default.toml");
+
+// ---- ファイルの形式 ----
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    languages: Vec<LanguageFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LanguageFile {
+    name: String,
+    // `team_experience` のキー。省略時は名前の小文字
+    experience: Option<String>,
+    performance: CriterionFile,
+    development_speed: CriterionFile,
+    maintenance: CriterionFile,
+    risk: CriterionFile,
+    // 後から追加した項目。以前の形式のファイルも読めるよう、省略時は中間の点数にする
+    // （デフォルトの重みは0なので、重みを付けない限り結果は変わらない）
+    #[serde(default)]
+    ecosystem_maturity: CriterionFile,
+    #[serde(default)]
+    hiring_pool: CriterionFile,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CriterionFile {
+    default: f64,
+    default_reason: Option<String>,
+    #[serde(default)]
+    rules: Vec<RuleFile>,
+}
+
+impl Default for CriterionFile {
+    fn default() -> Self {
+        CriterionFile {
+            default: MAX_SCORE / 2.0,
+            default_reason: Some("not rated in the rules file".to_string()),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    when: Vec<String>,
+    score: f64,
+    reason: Option<String>,
+}
+
+// ---- エラー ----
+
+/// ルールファイルを読み込めなかった理由
+#[derive(Debug)]
+pub enum RulesError {
+    Io { path: String, error: std::io::Error },
+    /// TOML/JSONとして読めない、または未知のキーがある
+    Parse { source_name: String, message: String },
+    /// 形式は正しいが、ルールの内容がおかしい
+    Invalid { rule: String, message: String },
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Io { path, error } => write!(f, "{}: {}", path, error),
+            RulesError::Parse { source_name, message } => write!(f, "{}: {}", source_name, message),
+            RulesError::Invalid { rule, message } => write!(f, "rule `{}`: {}", rule, message),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+fn invalid(rule: &str, message: impl Into<String>) -> RulesError {
+    RulesError::Invalid {
+        rule: rule.to_string(),
+        message: message.into(),
+    }
+}
+
+// ---- 条件式 ----
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flag {
+    PerformanceCritical,
+    MemoryConstraints,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Number {
+    DevelopmentTimeline,
+    MaintenancePeriod,
+    ConcurrentUsers,
+    DataVolumeGb,
+    // `None` は評価中の言語自身
+    Experience(Option<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Flag { flag: Flag, expected: bool },
+    Compare { field: Number, op: Op, value: f64 },
+}
+
+fn parse_flag(name: &str) -> Option<Flag> {
+    match name {
+        "performance_critical" => Some(Flag::PerformanceCritical),
+        "memory_constraints" => Some(Flag::MemoryConstraints),
+        _ => None,
+    }
+}
+
+fn parse_number(name: &str) -> Option<Number> {
+    match name {
+        "development_timeline" => Some(Number::DevelopmentTimeline),
+        "maintenance_period" => Some(Number::MaintenancePeriod),
+        "concurrent_users" => Some(Number::ConcurrentUsers),
+        "data_volume_gb" => Some(Number::DataVolumeGb),
+        "experience" => Some(Number::Experience(None)),
+        _ => name
+            .strip_prefix("experience.")
+            .filter(|language| !language.is_empty())
+            .map(|language| Number::Experience(Some(language.to_string()))),
+    }
+}
+
+// `performance_critical`、`!memory_constraints`、`concurrent_users > 10000` の形
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    match parts.as_slice() {
+        [name] => {
+            let (expected, name) = match name.strip_prefix('!') {
+                Some(name) => (false, name),
+                None => (true, *name),
+            };
+            match (parse_flag(name), parse_number(name)) {
+                (Some(flag), _) => Ok(Condition::Flag { flag, expected }),
+                (None, Some(_)) => Err(format!("`{}` is a number and needs a comparison, e.g. `{} > 0`", name, name)),
+                (None, None) => Err(format!("unknown field `{}`", name)),
+            }
+        }
+        [name, op, value] => {
+            let field = match (parse_number(name), parse_flag(name)) {
+                (Some(field), _) => field,
+                (None, Some(_)) => return Err(format!("`{}` is a flag and cannot be compared", name)),
+                (None, None) => return Err(format!("unknown field `{}`", name)),
+            };
+            let op = match *op {
+                "<" => Op::Lt,
+                "<=" => Op::Le,
+                ">" => Op::Gt,
+                ">=" => Op::Ge,
+                "==" => Op::Eq,
+                "!=" => Op::Ne,
+                _ => return Err(format!("unknown operator `{}`", op)),
+            };
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("`{}` is not a number", value))?;
+            Ok(Condition::Compare { field, op, value })
+        }
+        _ => Err(format!("cannot parse condition `{}`", text)),
+    }
+}
+
+fn flag_value(flag: Flag, req: &ProjectRequirements) -> bool {
+    match flag {
+        Flag::PerformanceCritical => req.performance_critical,
+        Flag::MemoryConstraints => req.memory_constraints,
+    }
+}
+
+fn number_value(field: &Number, req: &ProjectRequirements, experience_key: &str) -> i32 {
+    match field {
+        Number::DevelopmentTimeline => req.development_timeline,
+        Number::MaintenancePeriod => req.maintenance_period,
+        Number::ConcurrentUsers => req.concurrent_users,
+        Number::DataVolumeGb => req.data_volume_gb,
+        // 経験の記載がない言語は0年として扱う
+        Number::Experience(language) => {
+            let key = language.as_deref().unwrap_or(experience_key);
+            *req.team_experience.get(key).unwrap_or(&0)
+        }
+    }
+}
+
+impl Condition {
+    // 条件が参照している要件の実際の値
+    fn actual(&self, req: &ProjectRequirements, experience_key: &str) -> String {
+        match self {
+            Condition::Flag { flag, .. } => flag_value(*flag, req).to_string(),
+            Condition::Compare { field, .. } => number_value(field, req, experience_key).to_string(),
+        }
+    }
+
+    fn matches(&self, req: &ProjectRequirements, experience_key: &str) -> bool {
+        match self {
+            Condition::Flag { flag, expected } => flag_value(*flag, req) == *expected,
+            Condition::Compare { field, op, value } => {
+                let actual = number_value(field, req, experience_key) as f64;
+                match op {
+                    Op::Lt => actual < *value,
+                    Op::Le => actual <= *value,
+                    Op::Gt => actual > *value,
+                    Op::Ge => actual >= *value,
+                    Op::Eq => actual == *value,
+                    Op::Ne => actual != *value,
+                }
+            }
+        }
+    }
+}
+
+// ---- 検査済みのルール ----
+
+#[derive(Debug, Clone)]
+struct Rule {
+    conditions: Vec<Condition>,
+    // 説明用に、ファイルに書かれたままの条件式も残す
+    when: Vec<String>,
+    score: f64,
+    reason: Option<String>,
+}
+
+/// 1つの評価項目のルール
+#[derive(Debug, Clone)]
+pub struct CriterionRules {
+    default: f64,
+    default_reason: Option<String>,
+    rules: Vec<Rule>,
+}
+
+/// 当てはまったルールの条件の1つと、そのときの要件の値
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fact {
+    /// ルールファイルに書かれたままの条件式
+    pub condition: String,
+    pub actual: String,
+}
+
+/// スコアがどのルールから来たか
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Explanation {
+    /// 当てはまったルールの番号（0始まり）。`None` は `default`
+    pub rule: Option<usize>,
+    /// 当てはまったルールの条件（`default` なら空）
+    pub when: Vec<Fact>,
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for Explanation {
+    // `rules[1] (experience >= 1 && experience <= 2): 学習コスト` の形
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            Some(i) => {
+                let conditions: Vec<&str> = self.when.iter().map(|fact| fact.condition.as_str()).collect();
+                write!(f, "rules[{}] ({})", i, conditions.join(" && "))?
+            }
+            None => write!(f, "default")?,
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// 評価項目に当てはまったルール
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub score: f64,
+    pub explanation: Explanation,
+}
+
+impl CriterionRules {
+    pub fn evaluate(&self, req: &ProjectRequirements, experience_key: &str) -> Outcome {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.conditions.iter().all(|c| c.matches(req, experience_key)))
+            .map(|(i, rule)| Outcome {
+                score: rule.score,
+                explanation: Explanation {
+                    rule: Some(i),
+                    when: rule
+                        .conditions
+                        .iter()
+                        .zip(&rule.when)
+                        .map(|(condition, text)| Fact {
+                            condition: text.clone(),
+                            actual: condition.actual(req, experience_key),
+                        })
+                        .collect(),
+                    reason: rule.reason.clone(),
+                },
+            })
+            .unwrap_or_else(|| Outcome {
+                score: self.default,
+                explanation: Explanation {
+                    rule: None,
+                    when: Vec::new(),
+                    reason: self.default_reason.clone(),
+                },
+            })
+    }
+}
+
+/// 1つの言語の評価ルール
+#[derive(Debug, Clone)]
+pub struct LanguageRules {
+    pub name: String,
+    pub experience_key: String,
+    // `Criterion::index()` の順
+    criteria: Vec<CriterionRules>,
+}
+
+impl LanguageRules {
+    pub fn criterion(&self, criterion: Criterion) -> &CriterionRules {
+        &self.criteria[criterion.index()]
+    }
+}
+
+/// 読み込んで検査したルール一式
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    pub languages: Vec<LanguageRules>,
+}
+
+/// スコアの上限（下限は0）
+pub const MAX_SCORE: f64 = 10.0;
+
+fn check_score(rule: &str, score: f64) -> Result<f64, RulesError> {
+    if score.is_finite() && (0.0..=MAX_SCORE).contains(&score) {
+        Ok(score)
+    } else {
+        Err(invalid(rule, format!("score {} is outside 0..={}", score, MAX_SCORE)))
+    }
+}
+
+fn compile_criterion(path: &str, file: CriterionFile) -> Result<CriterionRules, RulesError> {
+    let default = check_score(&format!("{}.default", path), file.default)?;
+    let rules = file
+        .rules
+        .into_iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            let name = format!("{}.rules[{}]", path, i);
+            if rule.when.is_empty() {
+                return Err(invalid(&name, "`when` is empty; use `default` for the fallback score"));
+            }
+            let conditions = rule
+                .when
+                .iter()
+                .map(|text| parse_condition(text).map_err(|message| invalid(&name, message)))
+                .collect::<Result<_, _>>()?;
+            Ok(Rule {
+                conditions,
+                when: rule.when,
+                score: check_score(&name, rule.score)?,
+                reason: rule.reason,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(CriterionRules {
+        default,
+        default_reason: file.default_reason,
+        rules,
+    })
+}
+
+impl RuleSet {
+    /// 組み込みのルール
+    pub fn builtin() -> Self {
+        Self::from_toml("<builtin>", DEFAULT_RULES).expect("built-in rules must be valid")
+    }
+
+    /// 拡張子が `.json` ならJSON、それ以外はTOMLとして読む
+    pub fn load(path: &Path) -> Result<Self, RulesError> {
+        let source_name = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|error| RulesError::Io {
+            path: source_name.clone(),
+            error,
+        })?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&source_name, &text)
+        } else {
+            Self::from_toml(&source_name, &text)
+        }
+    }
+
+    pub fn from_toml(source_name: &str, text: &str) -> Result<Self, RulesError> {
+        let file = toml::from_str(text).map_err(|e| RulesError::Parse {
+            source_name: source_name.to_string(),
+            message: e.to_string(),
+        })?;
+        Self::compile(file)
+    }
+
+    pub fn from_json(source_name: &str, text: &str) -> Result<Self, RulesError> {
+        let file = serde_json::from_str(text).map_err(|e| RulesError::Parse {
+            source_name: source_name.to_string(),
+            message: e.to_string(),
+        })?;
+        Self::compile(file)
+    }
+
+    fn compile(file: RulesFile) -> Result<Self, RulesError> {
+        if file.languages.is_empty() {
+            return Err(invalid("languages", "no languages are defined"));
+        }
+        let mut languages: Vec<LanguageRules> = Vec::new();
+        for (i, language) in file.languages.into_iter().enumerate() {
+            let name = language.name.trim().to_string();
+            if name.is_empty() {
+                return Err(invalid(&format!("languages[{}]", i), "language name is empty"));
+            }
+            if languages.iter().any(|l| l.name.eq_ignore_ascii_case(&name)) {
+                return Err(invalid(&name, "language is defined more than once"));
+            }
+            // `Criterion::ALL` と同じ順に並べる
+            let files = [
+                language.performance,
+                language.development_speed,
+                language.maintenance,
+                language.risk,
+                language.ecosystem_maturity,
+                language.hiring_pool,
+            ];
+            let criteria = Criterion::ALL
+                .iter()
+                .zip(files)
+                .map(|(criterion, file)| compile_criterion(&format!("{}.{}", name, criterion.name()), file))
+                .collect::<Result<_, _>>()?;
+            languages.push(LanguageRules {
+                experience_key: language.experience.unwrap_or_else(|| name.to_lowercase()),
+                criteria,
+                name,
+            });
+        }
+        Ok(RuleSet { languages })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn requirements() -> ProjectRequirements {
+        ProjectRequirements {
+            performance_critical: true,
+            memory_constraints: false,
+            team_experience: HashMap::from([("rust".to_string(), 2)]),
+            weights: Default::default(),
+            development_timeline: 8,
+            maintenance_period: 10,
+            concurrent_users: 50000,
+            data_volume_gb: 500,
+        }
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(
+            parse_condition("!memory_constraints"),
+            Ok(Condition::Flag {
+                flag: Flag::MemoryConstraints,
+                expected: false
+            })
+        );
+        assert_eq!(
+            parse_condition("experience.go >= 3"),
+            Ok(Condition::Compare {
+                field: Number::Experience(Some("go".to_string())),
+                op: Op::Ge,
+                value: 3.0
+            })
+        );
+        assert!(parse_condition("concurent_users > 1").unwrap_err().contains("unknown field"));
+        assert!(parse_condition("concurrent_users").unwrap_err().contains("needs a comparison"));
+        assert!(parse_condition("performance_critical == 1").unwrap_err().contains("flag"));
+        assert!(parse_condition("data_volume_gb => 1").unwrap_err().contains("operator"));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = RuleSet::builtin();
+        let rust = &rules.languages[0];
+        let outcome = rust
+            .criterion(Criterion::DevelopmentSpeed)
+            .evaluate(&requirements(), &rust.experience_key);
+        assert_eq!(outcome.score, 6.0);
+        assert_eq!(outcome.explanation.rule, Some(1));
+        assert_eq!(outcome.explanation.to_string(), "rules[1] (experience >= 1 && experience <= 2)");
+        let actual: Vec<&str> = outcome.explanation.when.iter().map(|fact| fact.actual.as_str()).collect();
+        assert_eq!(actual, ["2", "2"]);
+
+        // 経験の記載がなければ0年
+        let cpp = rules.languages.iter().find(|l| l.name == "C++").unwrap();
+        let outcome = cpp.criterion(Criterion::Risk).evaluate(&requirements(), &cpp.experience_key);
+        assert_eq!(outcome.score, 3.0);
+        assert_eq!(outcome.explanation.to_string(), "rules[0] (experience < 3): メモリ管理のリスク");
+    }
+
+    #[test]
+    fn test_errors_name_the_rule() {
+        let json = r#"{"languages": [{
+            "name": "Zig",
+            "performance": {"default": 9},
+            "development_speed": {"default": 5, "rules": [
+                {"when": ["experience == 0"], "score": 3},
+                {"when": ["experiance > 2"], "score": 8}
+            ]},
+            "maintenance": {"default": 7},
+            "risk": {"default": 5},
+            "ecosystem_maturity": {"default": 4},
+            "hiring_pool": {"default": 2}
+        }]}"#;
+        let error = RuleSet::from_json("zig.json", json).unwrap_err().to_string();
+        assert_eq!(error, "rule `Zig.development_speed.rules[1]`: unknown field `experiance`");
+
+        let json = json.replace("\"experiance > 2\"", "\"experience > 2\"").replace("\"score\": 8", "\"score\": 11");
+        let error = RuleSet::from_json("zig.json", &json).unwrap_err().to_string();
+        assert_eq!(error, "rule `Zig.development_speed.rules[1]`: score 11 is outside 0..=10");
+
+        // 未知のキーは読み込みの段階で弾く
+        let error = RuleSet::from_toml("x.toml", "[[languages]]\nname = \"Zig\"\nspeed = 1\n").unwrap_err();
+        assert!(matches!(error, RulesError::Parse { .. }));
+    }
+
+    #[test]
+    fn test_rules_without_newer_criteria() {
+        // ecosystem_maturity / hiring_pool を追加する前の形式
+        let toml = r#"
+[[languages]]
+name = "Kotlin"
+
+[languages.performance]
+default = 6.0
+
+[languages.development_speed]
+default = 8.0
+
+[languages.maintenance]
+default = 8.0
+
+[languages.risk]
+default = 7.0
+rules = [{ when = ["experience == 0"], score = 5.0 }]
+"#;
+        let rules = RuleSet::from_toml("kotlin.toml", toml).unwrap();
+        let kotlin = &rules.languages[0];
+        for criterion in [Criterion::EcosystemMaturity, Criterion::HiringPool] {
+            let outcome = kotlin.criterion(criterion).evaluate(&requirements(), &kotlin.experience_key);
+            assert_eq!(outcome.score, MAX_SCORE / 2.0);
+            assert_eq!(outcome.explanation.to_string(), "default: not rated in the rules file");
+        }
+        assert_eq!(kotlin.criterion(Criterion::Risk).evaluate(&requirements(), "kotlin").score, 5.0);
+    }
+}
@@ -0,0 +1,191 @@
+// プロジェクト要件のファイル（JSON/YAML/TOML）を読み込む
+//
+// 1ファイルが1つのシナリオで、シナリオ名はファイル名（拡張子なし）。
+// `components` があるファイルは、複数のコンポーネントからなるシステムとして読む。
+// 書き方は `scenarios/` のサンプルを参照。
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::portfolio::SystemDescription;
+use crate::ProjectRequirements;
+
+/// 名前付きのプロジェクト要件
+#[derive(Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub requirements: ProjectRequirements,
+}
+
+/// 名前付きのシステムの記述
+#[derive(Debug)]
+pub struct System {
+    pub name: String,
+    pub description: SystemDescription,
+}
+
+/// 読み込んだファイルの中身
+#[derive(Debug)]
+pub enum Input {
+    Project(Scenario),
+    System(System),
+}
+
+/// 要件ファイルを読み込めなかった理由
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io { path: String, error: std::io::Error },
+    /// 拡張子から形式が分からない
+    UnknownFormat { path: String },
+    /// JSON/YAML/TOMLとして読めない、または未知のキーがある
+    Parse { path: String, message: String },
+    /// 形式は正しいが、内容がおかしい
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io { path, error } => write!(f, "{}: {}", path, error),
+            ScenarioError::UnknownFormat { path } => {
+                write!(f, "{}: unknown file type (expected .json, .yaml, .yml or .toml)", path)
+            }
+            ScenarioError::Parse { path, message } => write!(f, "{}: {}", path, message),
+            ScenarioError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+// どちらの形式かを見分けるためだけに読む（他のキーは無視する）
+#[derive(Deserialize)]
+struct Probe {
+    components: Option<IgnoredAny>,
+}
+
+fn parse_as<T: DeserializeOwned>(path: &str, format: Format, text: &str) -> Result<T, ScenarioError> {
+    let parsed = match format {
+        Format::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|message| ScenarioError::Parse {
+        path: path.to_string(),
+        message,
+    })
+}
+
+fn invalid(path: &str, message: String) -> ScenarioError {
+    ScenarioError::Invalid {
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// 拡張子で形式を判断して読み込む
+pub fn load(path: &Path) -> Result<Input, ScenarioError> {
+    let display = path.display().to_string();
+    let format = Format::from_path(path).ok_or_else(|| ScenarioError::UnknownFormat { path: display.clone() })?;
+    let text = std::fs::read_to_string(path).map_err(|error| ScenarioError::Io {
+        path: display.clone(),
+        error,
+    })?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| display.clone());
+    parse(name, &display, format, &text)
+}
+
+fn parse(name: String, path: &str, format: Format, text: &str) -> Result<Input, ScenarioError> {
+    let probe: Probe = parse_as(path, format, text)?;
+    if probe.components.is_none() {
+        let requirements: ProjectRequirements = parse_as(path, format, text)?;
+        requirements.weights.validate().map_err(|message| invalid(path, message))?;
+        return Ok(Input::Project(Scenario { name, requirements }));
+    }
+    let description: SystemDescription = parse_as(path, format, text)?;
+    description.validate().map_err(|message| invalid(path, message))?;
+    Ok(Input::System(System { name, description }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [(&str, Format, &str); 3] = [
+        ("high-performance-web.toml", Format::Toml, include_str!("../scenarios/high-performance-web.toml")),
+        ("prototype.yaml", Format::Yaml, include_str!("../scenarios/prototype.yaml")),
+        ("embedded.json", Format::Json, include_str!("../scenarios/embedded.json")),
+    ];
+
+    fn parse_project(path: &str, format: Format, text: &str) -> Result<Scenario, ScenarioError> {
+        match parse(path.to_string(), path, format, text)? {
+            Input::Project(scenario) => Ok(scenario),
+            Input::System(_) => panic!("{} was read as a system", path),
+        }
+    }
+
+    #[test]
+    fn test_samples_parse() {
+        for (file, format, text) in SAMPLES {
+            assert_eq!(Format::from_path(Path::new(file)), Some(format));
+            let scenario = parse_project(file, format, text).unwrap();
+            assert!(!scenario.requirements.team_experience.is_empty(), "{}", file);
+        }
+        assert_eq!(Format::from_path(Path::new("requirements.txt")), None);
+    }
+
+    #[test]
+    fn test_system_is_detected_by_components() {
+        let text = include_str!("../scenarios/platform.yaml");
+        match parse("platform".to_string(), "platform.yaml", Format::Yaml, text).unwrap() {
+            Input::System(system) => assert_eq!(system.description.components.len(), 4),
+            Input::Project(_) => panic!("expected a system"),
+        }
+
+        let broken = text.replacen("links: [parser]", "links: [parsr]", 1);
+        let error = parse("platform".to_string(), "platform.yaml", Format::Yaml, &broken).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "platform.yaml: component `batch-pipeline` links to unknown component `parsr`"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let yaml = "performance_critical: true\nmemory_constraints: false\nteam_experience: {}\n\
+                    development_timeline: 6\nmaintenance_period: 3\nconcurrent_users: 10\ndata_volume_gb: 1\n";
+        assert!(parse_project("x.yaml", Format::Yaml, yaml).is_ok());
+
+        // 綴りの間違いは未知のキーとして弾く
+        let typo = yaml.replace("concurrent_users", "concurent_users");
+        let error = parse_project("x.yaml", Format::Yaml, &typo).unwrap_err();
+        assert!(matches!(error, ScenarioError::Parse { .. }), "{}", error);
+
+        let negative = format!("{}weights:\n  risk: -1\n", yaml);
+        let error = parse_project("x.yaml", Format::Yaml, &negative).unwrap_err();
+        assert_eq!(error.to_string(), "x.yaml: weight of `risk` must be a non-negative number, got -1");
+    }
+}
@@ -0,0 +1,165 @@
+// 重みの感度分析
+//
+// 「リスクをもっと重視したら？」に答えるため、評価項目ごとに、重みを
+// どこまで変えると1位の言語が入れ替わるかを求める。
+//
+// 総合スコアは重み付き平均なので、1位 A と別の言語 B の差の符号は
+// D = Σ w_k (a_k - b_k) の符号で決まる（分母の重みの合計は共通で正）。
+// 項目 i の重みだけを δ 変えると差は D + δ (a_i - b_i) になるので、
+// δ = -D / (a_i - b_i) で2つが並ぶ。|δ| が最も小さい言語が次の1位になる。
+// 重みを変えた結果、重みの合計が0になる場合は平均が定義できないので除く。
+
+use serde::Serialize;
+
+use crate::criteria::{Criterion, Weights};
+use crate::LanguageScore;
+
+/// 1位が入れ替わる重み
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Flip {
+    /// この重みで1位と並び、これを超えると追い抜く
+    pub weight: f64,
+    /// 現在の重みからの変化量。正なら重みを上げ、負なら下げると入れ替わる
+    pub delta: f64,
+    pub new_leader: String,
+}
+
+/// 1つの評価項目の感度
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sensitivity {
+    pub criterion: Criterion,
+    pub current_weight: f64,
+    /// 重みをどう変えても1位が変わらなければ `None`
+    pub flip: Option<Flip>,
+}
+
+/// スコアの高い順に並んだ `ranking` について、項目ごとの感度を求める
+pub fn analyze(ranking: &[LanguageScore], weights: &Weights) -> Vec<Sensitivity> {
+    let Some((leader, others)) = ranking.split_first() else {
+        return Vec::new();
+    };
+
+    Criterion::ALL
+        .iter()
+        .map(|&criterion| {
+            let current_weight = weights.get(criterion);
+            let flip = others
+                .iter()
+                .filter_map(|other| {
+                    let margin: f64 = Criterion::ALL
+                        .iter()
+                        .map(|&c| weights.get(c) * (leader.score(c) - other.score(c)))
+                        .sum();
+                    let diff = leader.score(criterion) - other.score(criterion);
+                    if diff == 0.0 || !margin.is_finite() {
+                        return None;
+                    }
+                    let delta = -margin / diff;
+                    let weight = current_weight + delta;
+                    // 重みは負にできず、合計が0になる重みでは平均が定義できない
+                    if weight < 0.0 || weights.total() + delta <= 0.0 {
+                        return None;
+                    }
+                    Some(Flip {
+                        weight,
+                        delta,
+                        new_leader: other.language.clone(),
+                    })
+                })
+                .min_by(|a, b| a.delta.abs().total_cmp(&b.delta.abs()));
+            Sensitivity {
+                criterion,
+                current_weight,
+                flip,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(language: &str, scores: [f64; 6], weights: &Weights) -> LanguageScore {
+        LanguageScore {
+            language: language.to_string(),
+            total_score: weights.weighted_average(&scores),
+            scores,
+            explanations: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_flip_point_reverses_ranking() {
+        let weights = Weights::default();
+        // A は性能で、B はリスクで勝っている
+        let a = score("A", [9.0, 5.0, 5.0, 4.0, 5.0, 5.0], &weights);
+        let b = score("B", [6.0, 5.0, 5.0, 6.0, 5.0, 5.0], &weights);
+        let result = analyze(&[a, b], &weights);
+
+        // 差は 3 - 2 = 1。リスクの重みを 1 + 1/2 = 1.5 にすると並ぶ
+        let risk = &result[Criterion::Risk.index()];
+        assert_eq!(
+            risk.flip,
+            Some(Flip {
+                weight: 1.5,
+                delta: 0.5,
+                new_leader: "B".to_string()
+            })
+        );
+        // 性能の重みを 1 - 1/3 まで下げると並ぶ
+        let performance = result[Criterion::Performance.index()].flip.as_ref().unwrap();
+        assert!((performance.weight - 2.0 / 3.0).abs() < 1e-9);
+        assert!((performance.delta + 1.0 / 3.0).abs() < 1e-9);
+        // スコアが同じ項目の重みは結果に影響しない
+        assert_eq!(result[Criterion::Maintenance.index()].flip, None);
+    }
+
+    #[test]
+    fn test_weights_cannot_go_negative() {
+        let weights = Weights::default();
+        // B は A より全ての項目で低いので、どの重みでも追い抜けない
+        let a = score("A", [9.0; 6], &weights);
+        let b = score("B", [5.0; 6], &weights);
+        assert!(analyze(&[a, b], &weights).iter().all(|s| s.flip.is_none()));
+    }
+
+    #[test]
+    fn test_delta_tells_direction() {
+        let weights = Weights::default();
+        let a = score("A", [9.0, 5.0, 5.0, 4.0, 5.0, 5.0], &weights);
+        let b = score("B", [6.0, 5.0, 5.0, 6.0, 5.0, 5.0], &weights);
+        let result = analyze(&[a, b], &weights);
+
+        // B が勝っている項目は重みを上げ、A が勝っている項目は下げる
+        assert!(result[Criterion::Risk.index()].flip.as_ref().unwrap().delta > 0.0);
+        assert!(result[Criterion::Performance.index()].flip.as_ref().unwrap().delta < 0.0);
+    }
+
+    #[test]
+    fn test_skips_flips_that_zero_the_total_weight() {
+        // 性能だけに重みがあると、その重みを0にすれば並ぶが、平均は定義できない
+        let weights = Weights {
+            performance: 1.0,
+            development_speed: 0.0,
+            maintenance: 0.0,
+            risk: 0.0,
+            ecosystem_maturity: 0.0,
+            hiring_pool: 0.0,
+        };
+        let ranking = |weights: &Weights| {
+            [
+                score("A", [9.0, 5.0, 5.0, 4.0, 5.0, 5.0], weights),
+                score("B", [6.0, 5.0, 5.0, 6.0, 5.0, 5.0], weights),
+            ]
+        };
+        let result = analyze(&ranking(&weights), &weights);
+        assert_eq!(result[Criterion::Performance.index()].flip, None);
+        // 他の項目の重みを上げれば入れ替えられる
+        assert_eq!(result[Criterion::Risk.index()].flip.as_ref().unwrap().new_leader, "B");
+
+        // 重みが全て0なら、どの入れ替わりも意味を持たない
+        let zero = Weights { performance: 0.0, ..weights };
+        assert!(analyze(&ranking(&zero), &zero).iter().all(|s| s.flip.is_none()));
+    }
+}
@@ -0,0 +1,208 @@
+// `--serve` で起動するWebサービス
+//
+// JSONのAPIと、`cargo run` を知らなくても使えるフォームのUI（`static/index.html`）を
+// 提供する。共有リンク `/s/<id>` のシナリオは `ScenarioStore` に保存する。
+//
+// 保存の件数や容量に上限はなく、認証もない。信頼できるネットワーク（社内やlocalhost）
+// でだけ公開すること。
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Html,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::html;
+use crate::report::{self, Evaluation, Report};
+use crate::store::{ScenarioStore, StoredScenario};
+use crate::{ProjectRequirements, TechDecisionFramework};
+
+const INDEX_HTML: &str = include_str!("../static/index.html");
+
+pub struct AppState {
+    pub framework: TechDecisionFramework,
+    pub store: ScenarioStore,
+}
+
+type ApiError = (StatusCode, String);
+
+#[derive(Serialize)]
+struct LanguageInfo {
+    name: String,
+    /// `team_experience` のキー
+    experience: String,
+}
+
+#[derive(Serialize)]
+struct SavedScenario {
+    id: String,
+    url: String,
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(index_handler))
+        .route("/s/:id", get(index_handler))
+        .route("/s/:id/report", get(report_handler))
+        .route("/api/languages", get(languages_handler))
+        .route("/api/recommend", post(recommend_handler))
+        .route("/api/scenarios", post(save_handler))
+        .route("/api/scenarios/:id", get(load_handler))
+        .with_state(state)
+}
+
+pub async fn run(addr: SocketAddr, state: AppState) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("listening on http://{}", addr);
+    axum::serve(listener, router(Arc::new(state))).await
+}
+
+fn evaluate(state: &AppState, name: String, req: &ProjectRequirements) -> Result<Evaluation, ApiError> {
+    let ranking = state
+        .framework
+        .recommend_language(req)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    Ok(Evaluation::new(name, req.weights, ranking))
+}
+
+// ファイルの読み書きはブロックするので、別スレッドで行う
+async fn load_scenario(state: Arc<AppState>, id: String) -> Result<StoredScenario, ApiError> {
+    tokio::task::spawn_blocking(move || state.store.load(&id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "no such scenario".to_string()))
+}
+
+// `/s/<id>` でも同じページを返し、ページ側がURLからシナリオを読み込む
+async fn index_handler() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+async fn languages_handler(State(state): State<Arc<AppState>>) -> Json<Vec<LanguageInfo>> {
+    let languages = state
+        .framework
+        .rules
+        .languages
+        .iter()
+        .map(|language| LanguageInfo {
+            name: language.name.clone(),
+            experience: language.experience_key.clone(),
+        })
+        .collect();
+    Json(languages)
+}
+
+async fn recommend_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ProjectRequirements>,
+) -> Result<Json<Value>, ApiError> {
+    let evaluation = evaluate(&state, "request".to_string(), &req)?;
+    Ok(Json(report::evaluation_json(&evaluation)))
+}
+
+async fn save_handler(
+    State(state): State<Arc<AppState>>,
+    Json(scenario): Json<StoredScenario>,
+) -> Result<(StatusCode, Json<SavedScenario>), ApiError> {
+    // 評価できないシナリオは保存しない
+    scenario
+        .requirements
+        .weights
+        .validate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let id = tokio::task::spawn_blocking(move || state.store.save(&scenario))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => (StatusCode::CONFLICT, e.to_string()),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    let url = format!("/s/{}", id);
+    Ok((StatusCode::CREATED, Json(SavedScenario { id, url })))
+}
+
+async fn load_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<StoredScenario>, ApiError> {
+    Ok(Json(load_scenario(state, id).await?))
+}
+
+// ADRに貼るためのHTMLレポート
+async fn report_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Html<String>, ApiError> {
+    let scenario = load_scenario(state.clone(), id).await?;
+    let evaluation = evaluate(&state, scenario.name, &scenario.requirements)?;
+    Ok(Html(html::render(&[Report::Project(evaluation)])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    // テストの最後に `remove_dir_all` で保存先を消すこと
+    fn state(name: &str) -> (Arc<AppState>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("decision-server-{}-{}", name, std::process::id()));
+        let state = Arc::new(AppState {
+            framework: TechDecisionFramework::new(RuleSet::builtin()),
+            store: ScenarioStore::open(&dir).unwrap(),
+        });
+        (state, dir)
+    }
+
+    fn requirements(json: &str) -> ProjectRequirements {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const REQUIREMENTS: &str = r#"{"performance_critical": true, "memory_constraints": true,
+        "team_experience": {"rust": 1}, "development_timeline": 8, "maintenance_period": 10,
+        "concurrent_users": 50000, "data_volume_gb": 500}"#;
+
+    #[tokio::test]
+    async fn test_recommend() {
+        let (state, dir) = state("recommend");
+        let Json(value) = recommend_handler(State(state.clone()), Json(requirements(REQUIREMENTS)))
+            .await
+            .unwrap();
+        assert_eq!(value["ranking"][0]["rank"], 1);
+        assert_eq!(value["ranking"].as_array().unwrap().len(), 5);
+
+        let invalid = REQUIREMENTS.replace("\"data_volume_gb\": 500", "\"data_volume_gb\": 500, \"weights\": {\"risk\": -1}");
+        let (status, _) = recommend_handler(State(state), Json(requirements(&invalid)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_share_link_round_trip() {
+        let (state, dir) = state("share");
+        let scenario = StoredScenario {
+            name: "Web & API".to_string(),
+            requirements: requirements(REQUIREMENTS),
+        };
+        let (status, Json(saved)) = save_handler(State(state.clone()), Json(scenario)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(saved.url, format!("/s/{}", saved.id));
+
+        let Json(loaded) = load_handler(State(state.clone()), Path(saved.id.clone())).await.unwrap();
+        assert_eq!(loaded.name, "Web & API");
+        let Html(report) = report_handler(State(state.clone()), Path(saved.id)).await.unwrap();
+        assert!(report.contains("<h2>Web &amp; API</h2>"));
+
+        let (status, _) = load_handler(State(state), Path("missing".to_string())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+// 共有リンク用のシナリオの保存先
+//
+// シナリオは `<id>.json` としてディレクトリに保存する。IDは内容のハッシュなので、
+// 同じシナリオを何度共有しても同じリンクになる。
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::ProjectRequirements;
+
+/// 保存されるシナリオ
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StoredScenario {
+    pub name: String,
+    pub requirements: ProjectRequirements,
+}
+
+pub struct ScenarioStore {
+    dir: PathBuf,
+}
+
+// IDの長さ（16進数の桁数）。SHA-256の先頭128ビットを使う
+const ID_LEN: usize = 32;
+
+// 内容からIDを作る。IDは推測されたり衝突させられたりしないよう、暗号学的ハッシュを使う
+// （`DefaultHasher` はRustのバージョンで結果が変わりうるので使わない）
+fn content_id(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest[..ID_LEN / 2].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// IDは32桁の16進数。それ以外はファイル名に使わない
+fn is_valid_id(id: &str) -> bool {
+    id.len() == ID_LEN && id.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+}
+
+impl ScenarioStore {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(ScenarioStore { dir: dir.to_path_buf() })
+    }
+
+    /// 保存してIDを返す
+    pub fn save(&self, scenario: &StoredScenario) -> io::Result<String> {
+        let json = serde_json::to_vec_pretty(scenario)?;
+        let id = content_id(&json);
+        let path = self.dir.join(format!("{}.json", id));
+        match std::fs::read(&path) {
+            // 同じ内容は保存済み
+            Ok(existing) if existing == json => {}
+            // IDが衝突した別のシナリオを、新しいリンクで返してはいけない
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("scenario {} already exists with different contents", id),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // 書きかけのファイルを読まれないよう、別名で書いてから置き換える
+                let tmp = self.dir.join(format!("{}.json.tmp", id));
+                std::fs::write(&tmp, &json)?;
+                std::fs::rename(&tmp, &path)?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(id)
+    }
+
+    /// IDが正しくない、または見つからなければ `None`
+    pub fn load(&self, id: &str) -> io::Result<Option<StoredScenario>> {
+        if !is_valid_id(id) {
+            return Ok(None);
+        }
+        match std::fs::read(self.dir.join(format!("{}.json", id))) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn scenario(users: i32) -> StoredScenario {
+        StoredScenario {
+            name: "web".to_string(),
+            requirements: ProjectRequirements {
+                performance_critical: true,
+                memory_constraints: false,
+                team_experience: HashMap::from([("rust".to_string(), 2)]),
+                development_timeline: 6,
+                maintenance_period: 5,
+                concurrent_users: users,
+                data_volume_gb: 10,
+                weights: Default::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("decision-store-{}", std::process::id()));
+        let store = ScenarioStore::open(&dir).unwrap();
+
+        let id = store.save(&scenario(100)).unwrap();
+        // 同じ内容なら同じID、違えば別のID
+        assert_eq!(store.save(&scenario(100)).unwrap(), id);
+        assert_ne!(store.save(&scenario(200)).unwrap(), id);
+
+        let loaded = store.load(&id).unwrap().unwrap();
+        assert_eq!(loaded.requirements.concurrent_users, 100);
+        assert_eq!(id.len(), 32);
+        assert!(store.load(&"0".repeat(32)).unwrap().is_none());
+        // ディレクトリの外は読まない
+        assert!(store.load("../../etc/passwd").unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_id_does_not_depend_on_map_order() {
+        let dir = std::env::temp_dir().join(format!("decision-store-order-{}", std::process::id()));
+        let store = ScenarioStore::open(&dir).unwrap();
+
+        // 別々に作った `HashMap` は反復順が異なりうる
+        let with_languages = |languages: &[(&str, i32)]| {
+            let mut s = scenario(100);
+            s.requirements.team_experience =
+                languages.iter().map(|&(name, years)| (name.to_string(), years)).collect();
+            s
+        };
+        let languages = [("rust", 2), ("go", 5), ("python", 8), ("java", 10), ("c++", 3)];
+        let mut reversed = languages;
+        reversed.reverse();
+        let id = store.save(&with_languages(&languages)).unwrap();
+        for _ in 0..10 {
+            assert_eq!(store.save(&with_languages(&languages)).unwrap(), id);
+            assert_eq!(store.save(&with_languages(&reversed)).unwrap(), id);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_different_contents_under_same_id() {
+        let dir = std::env::temp_dir().join(format!("decision-store-collision-{}", std::process::id()));
+        let store = ScenarioStore::open(&dir).unwrap();
+
+        let id = store.save(&scenario(100)).unwrap();
+        // 同じIDのファイルを別の内容で置き換えて、衝突を再現する
+        let other = serde_json::to_vec_pretty(&scenario(200)).unwrap();
+        std::fs::write(dir.join(format!("{}.json", id)), other).unwrap();
+
+        let err = store.save(&scenario(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(store.load(&id).unwrap().unwrap().requirements.concurrent_users, 200);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+use chapter01_benchmark::{
+    fibonacci, fibonacci_iterative, Big, Checked, FastDoubling, Fibonacci, Memoized, ParallelRecursive, Recursive,
+    Wrapping, MAX_U64_INDEX, TEST_VALUES,
+};
+use criterion::measurement::WallTime;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+
+fn fibonacci_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci");
+    
+    for n in [10u64, 20, 30].iter() {
+        group.bench_with_input(BenchmarkId::new("recursive", n), n, |b, &n| {
+            b.iter(|| fibonacci(black_box(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", n), n, |b, &n| {
+            b.iter(|| fibonacci_iterative(black_box(n)))
+        });
+    }
+    
+    group.finish();
+}
+
+fn bench_variant<F: Fibonacci>(group: &mut BenchmarkGroup<WallTime>, variant: &F, n: u64) {
+    group.bench_with_input(BenchmarkId::new(variant.name(), n), &n, |b, &n| {
+        b.iter(|| variant.fib(black_box(n)))
+    });
+}
+
+// 溢れ方の違う実装と任意精度の実装を比べる
+fn variants_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci_variants");
+    
+    // u64 に収まる範囲では全部
+    bench_variant(&mut group, &Checked, MAX_U64_INDEX);
+    bench_variant(&mut group, &Wrapping, MAX_U64_INDEX);
+    bench_variant(&mut group, &Big, MAX_U64_INDEX);
+    bench_variant(&mut group, &FastDoubling, MAX_U64_INDEX);
+    
+    // それより先は任意精度の実装だけ（O(n) と O(log n) の差が見える）
+    for n in [1_000u64, 10_000, 100_000] {
+        bench_variant(&mut group, &Big, n);
+        bench_variant(&mut group, &FastDoubling, n);
+    }
+    
+    group.finish();
+}
+
+// 指数時間の再帰を、メモ化と並列化でどこまで速くできるか（`main` と同じ n で）
+fn recursive_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci_recursive");
+    let parallel = ParallelRecursive::default();
+    
+    for n in TEST_VALUES {
+        // 素朴な再帰は fib(35) で数十ms、fib(40) で0.5秒以上かかるので、サンプルを減らす
+        group.sample_size(if n >= 35 { 10 } else { 100 });
+        bench_variant(&mut group, &Recursive, n);
+        bench_variant(&mut group, &Memoized, n);
+        bench_variant(&mut group, &parallel, n);
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, fibonacci_benchmark, variants_benchmark, recursive_benchmark);
+criterion_main!(benches);
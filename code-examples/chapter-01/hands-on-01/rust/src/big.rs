@@ -0,0 +1,193 @@
+// フィボナッチ数に必要なだけの多倍長の符号なし整数
+//
+// 足し算・引き算・掛け算（筆算）と10進の表示だけを持つ。
+// 値は32ビットの桁（リム）を下位から並べたもので、上位の0は持たない。
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// 2進での桁数
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(top) => (self.limbs.len() as u64 - 1) * 32 + (32 - top.leading_zeros() as u64),
+            None => 0,
+        }
+    }
+
+    /// `u64` に収まらなければ `None`
+    pub fn to_u64(&self) -> Option<u64> {
+        (self.limbs.len() <= 2).then(|| self.low_u64())
+    }
+
+    /// 下位64ビット（2^64 で割った余り）
+    pub fn low_u64(&self) -> u64 {
+        let limb = |i: usize| self.limbs.get(i).copied().unwrap_or(0) as u64;
+        limb(0) | (limb(1) << 32)
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    // 10進で表示するために、小さな数で割って余りを返す
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder: u64 = 0;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        self.normalize();
+        remainder as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        let mut big = BigUint {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        };
+        big.normalize();
+        big
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, other: &BigUint) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0u64;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + other.limbs.get(i).copied().unwrap_or(0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+            if carry == 0 && i >= other.limbs.len() {
+                break;
+            }
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut sum = self.clone();
+        sum += other;
+        sum
+    }
+}
+
+/// `self < other` ならパニックする（符号なしなので）
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(self.limbs.len() >= other.limbs.len(), "BigUint subtraction underflow");
+        let mut difference = self.clone();
+        let mut borrow = 0i64;
+        for (i, limb) in difference.limbs.iter_mut().enumerate() {
+            let value = *limb as i64 - other.limbs.get(i).copied().unwrap_or(0) as i64 - borrow;
+            borrow = (value < 0) as i64;
+            *limb = (value + (borrow << 32)) as u32;
+        }
+        assert!(borrow == 0, "BigUint subtraction underflow");
+        difference.normalize();
+        difference
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                // (2^32 - 1)^2 + 2 * (2^32 - 1) は u64 に収まる
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        let mut product = BigUint { limbs };
+        product.normalize();
+        product
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+        // 10^9 ごとに区切って下の桁から求める
+        let mut rest = self.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(1_000_000_000));
+        }
+        let mut digits = chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_and_display() {
+        let max = BigUint::from(u64::MAX);
+        let one = BigUint::from(1);
+        let sum = &max + &one;
+        assert_eq!(sum.to_string(), "18446744073709551616");
+        assert_eq!((sum.to_u64(), sum.low_u64(), sum.bits()), (None, 0, 65));
+        assert_eq!(&sum - &one, max);
+        assert_eq!((&max * &max).to_string(), "340282366920938463426481119284349108225");
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(format!("{:>5}", BigUint::from(42)), "   42");
+        // 10^9 の区切りで途中の0を落とさない
+        assert_eq!(BigUint::from(5_000_000_000_000_000_007).to_string(), "5000000000000000007");
+    }
+
+    #[test]
+    #[should_panic(expected = "underflow")]
+    fn test_sub_underflow() {
+        let _ = &BigUint::from(1) - &BigUint::from(2);
+    }
+}
@@ -0,0 +1,270 @@
+// フィボナッチ数列の実装（`main` と criterion のベンチマークで共有する）
+//
+// `fibonacci` / `fibonacci_iterative` は言語間で比べるための素朴な実装。
+// `u64` に収まるのは F(93) までなので、溢れ方の違う実装と、任意精度の実装を
+// `Fibonacci` トレイトの後ろに並べて、同じベンチマークで比べられるようにしている。
+// 指数時間の再帰そのものを直す例として、メモ化と並列化した版も並べる。
+
+pub mod big;
+pub mod memo;
+
+pub use big::BigUint;
+pub use memo::Memo;
+
+/// `main` で計測する n
+pub const TEST_VALUES: [u64; 5] = [10, 20, 30, 35, 40];
+
+pub fn fibonacci(n: u64) -> u64 {
+    match n {
+        0 => 0,
+        1 => 1,
+        _ => fibonacci(n - 1) + fibonacci(n - 2),
+    }
+}
+
+/// n > 93 では溢れる（デバッグビルドではパニック、リリースビルドでは黙って折り返す）
+pub fn fibonacci_iterative(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    if n == 1 {
+        return 1;
+    }
+    
+    let mut prev = 0;
+    let mut curr = 1;
+    
+    for _ in 2..=n {
+        let next = prev + curr;
+        prev = curr;
+        curr = next;
+    }
+    
+    curr
+}
+
+/// `u64` に収まる最大のフィボナッチ数の添字
+pub const MAX_U64_INDEX: u64 = 93;
+
+/// ベンチマークで比べるための共通のインターフェース
+pub trait Fibonacci {
+    type Output;
+
+    /// ベンチマークのID
+    fn name(&self) -> &'static str;
+
+    fn fib(&self, n: u64) -> Self::Output;
+}
+
+/// 素朴な再帰（`fibonacci`）。O(φ^n)
+pub struct Recursive;
+
+/// 同じ再帰を `Memo` でメモ化したもの。O(n)
+///
+/// 呼び出しごとに空のキャッシュから始めるので、ベンチマークの繰り返しで前回の結果は使わない。
+pub struct Memoized;
+
+/// 再帰の2つの枝を rayon で並列に計算する。n が `cutoff` 以下になったら逐次の再帰に切り替える
+///
+/// 計算量は素朴な再帰と同じで、コア数の分だけ速くなるだけ。
+/// 小さな n まで分割するとタスクの生成のほうが高くつくので、`cutoff` で止める。
+pub struct ParallelRecursive {
+    pub cutoff: u64,
+}
+
+impl Default for ParallelRecursive {
+    fn default() -> Self {
+        ParallelRecursive { cutoff: 20 }
+    }
+}
+
+/// 溢れたら `None`（n > 93）
+pub struct Checked;
+
+/// 2^64 で割った余り。ビルドの種類によらず同じ値になる
+pub struct Wrapping;
+
+/// 多倍長整数で1つずつ足していく。O(n) 回の足し算
+pub struct Big;
+
+/// 高速倍加法。O(log n) 回の掛け算
+///
+/// F(2k) = F(k) * (2F(k+1) - F(k)), F(2k+1) = F(k)^2 + F(k+1)^2
+pub struct FastDoubling;
+
+impl Fibonacci for Recursive {
+    type Output = u64;
+
+    fn name(&self) -> &'static str {
+        "recursive"
+    }
+
+    fn fib(&self, n: u64) -> u64 {
+        fibonacci(n)
+    }
+}
+
+impl Fibonacci for Memoized {
+    type Output = u64;
+
+    fn name(&self) -> &'static str {
+        "memoized"
+    }
+
+    fn fib(&self, n: u64) -> u64 {
+        let memo = Memo::new(|recurse: &dyn Fn(u64) -> u64, n: u64| match n {
+            0 => 0,
+            1 => 1,
+            _ => recurse(n - 1) + recurse(n - 2),
+        });
+        memo.get(n)
+    }
+}
+
+impl ParallelRecursive {
+    fn fork_join(&self, n: u64) -> u64 {
+        if n <= self.cutoff.max(1) {
+            return fibonacci(n);
+        }
+        let (a, b) = rayon::join(|| self.fork_join(n - 1), || self.fork_join(n - 2));
+        a + b
+    }
+}
+
+impl Fibonacci for ParallelRecursive {
+    type Output = u64;
+
+    fn name(&self) -> &'static str {
+        "parallel"
+    }
+
+    fn fib(&self, n: u64) -> u64 {
+        self.fork_join(n)
+    }
+}
+
+impl Fibonacci for Checked {
+    type Output = Option<u64>;
+
+    fn name(&self) -> &'static str {
+        "checked"
+    }
+
+    fn fib(&self, n: u64) -> Option<u64> {
+        if n == 0 {
+            return Some(0);
+        }
+        // F(n+1) は求めない（F(93) のときに溢れてしまうので）
+        let (mut prev, mut curr) = (0u64, 1u64);
+        for _ in 1..n {
+            let next = prev.checked_add(curr)?;
+            prev = curr;
+            curr = next;
+        }
+        Some(curr)
+    }
+}
+
+impl Fibonacci for Wrapping {
+    type Output = u64;
+
+    fn name(&self) -> &'static str {
+        "wrapping"
+    }
+
+    fn fib(&self, n: u64) -> u64 {
+        let (mut prev, mut curr) = (0u64, 1u64);
+        for _ in 0..n {
+            let next = prev.wrapping_add(curr);
+            prev = curr;
+            curr = next;
+        }
+        prev
+    }
+}
+
+impl Fibonacci for Big {
+    type Output = BigUint;
+
+    fn name(&self) -> &'static str {
+        "big"
+    }
+
+    fn fib(&self, n: u64) -> BigUint {
+        let (mut prev, mut curr) = (BigUint::zero(), BigUint::from(1));
+        for _ in 0..n {
+            // (prev, curr) = (curr, prev + curr) を確保し直さずに行う
+            prev += &curr;
+            std::mem::swap(&mut prev, &mut curr);
+        }
+        prev
+    }
+}
+
+impl Fibonacci for FastDoubling {
+    type Output = BigUint;
+
+    fn name(&self) -> &'static str {
+        "fast_doubling"
+    }
+
+    fn fib(&self, n: u64) -> BigUint {
+        // (a, b) = (F(k), F(k+1)) として、n の上位ビットから k を組み立てる
+        let (mut a, mut b) = (BigUint::zero(), BigUint::from(1));
+        for bit in (0..u64::BITS - n.leading_zeros()).rev() {
+            let twice_b = &b + &b;
+            let c = &a * &(&twice_b - &a);
+            let d = &(&a * &a) + &(&b * &b);
+            if (n >> bit) & 1 == 1 {
+                b = &c + &d;
+                a = d;
+            } else {
+                a = c;
+                b = d;
+            }
+        }
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(Checked.fib(MAX_U64_INDEX), Some(12_200_160_415_121_876_738));
+        assert_eq!(Checked.fib(MAX_U64_INDEX + 1), None);
+        assert_eq!(FastDoubling.fib(100).to_string(), "354224848179261915075");
+        // F(94) = 19740274219868223167 を 2^64 で割った余り
+        assert_eq!(Wrapping.fib(94), 19_740_274_219_868_223_167u128 as u64);
+        assert_eq!(Big.fib(0), BigUint::zero());
+    }
+
+    #[test]
+    fn test_variants_agree() {
+        for n in 0..=300 {
+            let exact = Big.fib(n);
+            assert_eq!(FastDoubling.fib(n), exact, "fast doubling, n = {}", n);
+            assert_eq!(Checked.fib(n), exact.to_u64(), "checked, n = {}", n);
+            assert_eq!(Wrapping.fib(n), exact.low_u64(), "wrapping, n = {}", n);
+            if n <= MAX_U64_INDEX {
+                assert_eq!(fibonacci_iterative(n), Wrapping.fib(n), "iterative, n = {}", n);
+            }
+            if n <= 25 {
+                assert_eq!(fibonacci(n), fibonacci_iterative(n), "recursive, n = {}", n);
+            }
+        }
+        // 再帰の3つの版
+        let parallel = ParallelRecursive { cutoff: 5 };
+        for n in 0..=27 {
+            let expected = fibonacci_iterative(n);
+            assert_eq!(Recursive.fib(n), expected, "recursive, n = {}", n);
+            assert_eq!(Memoized.fib(n), expected, "memoized, n = {}", n);
+            assert_eq!(parallel.fib(n), expected, "parallel, n = {}", n);
+        }
+        assert_eq!(Memoized.fib(MAX_U64_INDEX), Checked.fib(MAX_U64_INDEX).unwrap());
+        // 大きな n でも O(n) と O(log n) の実装が一致する
+        assert_eq!(FastDoubling.fib(5_000), Big.fib(5_000));
+    }
+}
@@ -1,52 +1,51 @@
-use std::time::Instant;
+use bench_harness::Runner;
+use chapter01_benchmark::{
+    fibonacci, fibonacci_iterative, Big, Checked, FastDoubling, Fibonacci, Memoized, ParallelRecursive, Recursive,
+    Wrapping, MAX_U64_INDEX, TEST_VALUES,
+};
+use std::hint::black_box;
 
-fn fibonacci(n: u64) -> u64 {
-    match n {
-        0 => 0,
-        1 => 1,
-        _ => fibonacci(n - 1) + fibonacci(n - 2),
-    }
-}
-
-fn fibonacci_iterative(n: u64) -> u64 {
-    if n == 0 {
-        return 0;
-    }
-    if n == 1 {
-        return 1;
-    }
-    
-    let mut prev = 0;
-    let mut curr = 1;
-    
-    for _ in 2..=n {
-        let next = prev + curr;
-        prev = curr;
-        curr = next;
-    }
-    
-    curr
+fn bench_variant<F: Fibonacci>(runner: &mut Runner, variant: &F, n: u64) {
+    runner.bench(variant.name(), n, || variant.fib(black_box(n)));
 }
 
 fn main() {
     println!("Rust Performance Benchmark - Chapter 01");
     println!("=========================================");
     
-    let test_values = vec![10, 20, 30, 35, 40];
+    let mut runner = Runner::from_args("chapter01-fibonacci");
     
+    // 素朴な再帰と、それをメモ化・並列化したもの
     println!("\n再帰的フィボナッチ:");
-    for n in &test_values {
-        let start = Instant::now();
-        let result = fibonacci(*n);
-        let duration = start.elapsed();
-        println!("  fib({}) = {} | 時間: {:?}", n, result, duration);
+    let parallel = ParallelRecursive::default();
+    for n in TEST_VALUES {
+        println!("  fib({}) = {}", n, fibonacci(n));
+        bench_variant(&mut runner, &Recursive, n);
+        bench_variant(&mut runner, &Memoized, n);
+        bench_variant(&mut runner, &parallel, n);
     }
     
     println!("\n反復的フィボナッチ:");
-    for n in &test_values {
-        let start = Instant::now();
-        let result = fibonacci_iterative(*n);
-        let duration = start.elapsed();
-        println!("  fib({}) = {} | 時間: {:?}", n, result, duration);
+    for n in TEST_VALUES {
+        println!("  fib({}) = {}", n, fibonacci_iterative(n));
+        runner.bench("fibonacci_iterative", n, || fibonacci_iterative(black_box(n)));
+    }
+    
+    // u64 は F(93) まで。その先は溢れ方の違う実装と任意精度の実装を比べる
+    println!("\nオーバーフロー対策と任意精度:");
+    let n = MAX_U64_INDEX + 1;
+    println!("  checked:  fib({}) = {:?}", n, Checked.fib(n));
+    println!("  wrapping: fib({}) = {} (mod 2^64)", n, Wrapping.fib(n));
+    println!("  big:      fib({}) = {}", n, Big.fib(n));
+    bench_variant(&mut runner, &Checked, MAX_U64_INDEX);
+    bench_variant(&mut runner, &Wrapping, MAX_U64_INDEX);
+    bench_variant(&mut runner, &Big, MAX_U64_INDEX);
+    bench_variant(&mut runner, &FastDoubling, MAX_U64_INDEX);
+    for n in [1_000, 10_000] {
+        println!("  fib({}) has {} digits", n, FastDoubling.fib(n).to_string().len());
+        bench_variant(&mut runner, &Big, n);
+        bench_variant(&mut runner, &FastDoubling, n);
     }
-}
\ No newline at end of file
+    
+    runner.finish();
+}
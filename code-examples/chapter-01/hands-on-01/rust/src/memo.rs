@@ -0,0 +1,78 @@
+// 純粋な再帰関数のメモ化
+//
+// 再帰呼び出しを自分の名前ではなく引数の `recurse` 経由で書いた関数を包み、
+// 一度計算した引数の結果はキャッシュから返す。引数と結果は `Clone` できればよい。
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub struct Memo<A, R, F> {
+    f: F,
+    cache: RefCell<HashMap<A, R>>,
+}
+
+impl<A, R, F> Memo<A, R, F>
+where
+    A: Eq + Hash + Clone,
+    R: Clone,
+    F: Fn(&dyn Fn(A) -> R, A) -> R,
+{
+    pub fn new(f: F) -> Self {
+        Memo {
+            f,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, arg: A) -> R {
+        if let Some(result) = self.cache.borrow().get(&arg) {
+            return result.clone();
+        }
+        // 再帰の途中でキャッシュを借りたままにしない
+        let result = (self.f)(&|arg| self.get(arg), arg.clone());
+        self.cache.borrow_mut().insert(arg, result.clone());
+        result
+    }
+
+    /// キャッシュしている結果の数
+    pub fn cached(&self) -> usize {
+        self.cache.borrow().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_each_argument_is_computed_once() {
+        let calls = Cell::new(0);
+        let fib = Memo::new(|recurse: &dyn Fn(u64) -> u64, n: u64| {
+            calls.set(calls.get() + 1);
+            if n < 2 {
+                n
+            } else {
+                recurse(n - 1) + recurse(n - 2)
+            }
+        });
+        assert_eq!(fib.get(50), 12_586_269_025);
+        assert_eq!((calls.get(), fib.cached()), (51, 51));
+        assert_eq!(fib.get(40), 102_334_155);
+        assert_eq!(calls.get(), 51);
+    }
+
+    #[test]
+    fn test_tuple_arguments() {
+        // 二項係数（パスカルの三角形）
+        let binomial = Memo::new(|recurse: &dyn Fn((u64, u64)) -> u64, (n, k): (u64, u64)| {
+            if k == 0 || k == n {
+                1
+            } else {
+                recurse((n - 1, k - 1)) + recurse((n - 1, k))
+            }
+        });
+        assert_eq!(binomial.get((60, 30)), 118_264_581_564_861_424);
+    }
+}
@@ -0,0 +1,334 @@
+// `Record` を列ごとに持つテーブル
+//
+// `Vec<Record>` は1件ごとに `String` を確保し、id・name・value が交互に並ぶ。
+// ここでは id と value をそれぞれ1本の `Vec` に、name は1つのバッファ（アリーナ）に
+// 詰めて持つ。絞り込みの結果は1行1ビットのビットマップで表し、集計は必要な列だけを読む。
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::Record;
+
+/// 文字列を1つのバッファに続けて格納する
+#[derive(Debug, Default)]
+pub struct StringArena {
+    bytes: String,
+    /// i 番目の文字列は `bytes[ends[i-1]..ends[i]]`
+    ends: Vec<usize>,
+}
+
+impl StringArena {
+    pub fn with_capacity(strings: usize, bytes: usize) -> Self {
+        StringArena {
+            bytes: String::with_capacity(bytes),
+            ends: Vec::with_capacity(strings),
+        }
+    }
+
+    pub fn push(&mut self, s: &str) {
+        self.bytes.push_str(s);
+        self.ends.push(self.bytes.len());
+    }
+
+    pub fn get(&self, index: usize) -> &str {
+        let start = if index == 0 { 0 } else { self.ends[index - 1] };
+        &self.bytes[start..self.ends[index]]
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    /// 使っているヒープの大きさ（容量ベース）
+    pub fn heap_bytes(&self) -> usize {
+        self.bytes.capacity() + self.ends.capacity() * std::mem::size_of::<usize>()
+    }
+}
+
+/// 行の集合。i 行目が含まれていれば i ビット目が1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Bitmap {
+    /// `f(i)` が真の行を含む
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        let mut words = Vec::with_capacity(len.div_ceil(64));
+        for start in (0..len).step_by(64) {
+            let mut word = 0u64;
+            for bit in 0..(len - start).min(64) {
+                word |= (f(start + bit) as u64) << bit;
+            }
+            words.push(word);
+        }
+        Bitmap { words, len }
+    }
+
+    /// すべての行を含む
+    pub fn full(len: usize) -> Self {
+        let mut bitmap = Bitmap {
+            words: vec![u64::MAX; len.div_ceil(64)],
+            len,
+        };
+        bitmap.clear_tail();
+        bitmap
+    }
+
+    // 最後のワードの、行のない部分のビットを0にする
+    fn clear_tail(&mut self) {
+        let tail = self.len % 64;
+        if tail != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1u64 << tail) - 1;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, row: usize) -> bool {
+        row < self.len && self.words[row / 64] >> (row % 64) & 1 == 1
+    }
+
+    /// 含まれる行の数
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn combine(&self, other: &Bitmap, op: impl Fn(u64, u64) -> u64) -> Bitmap {
+        assert_eq!(self.len, other.len, "bitmaps of different lengths");
+        Bitmap {
+            words: self.words.iter().zip(&other.words).map(|(&a, &b)| op(a, b)).collect(),
+            len: self.len,
+        }
+    }
+
+    pub fn and(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn or(&self, other: &Bitmap) -> Bitmap {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn not(&self) -> Bitmap {
+        let mut inverted = Bitmap {
+            words: self.words.iter().map(|w| !w).collect(),
+            len: self.len,
+        };
+        inverted.clear_tail();
+        inverted
+    }
+
+    /// 含まれる行の番号（昇順）
+    pub fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// 列の値で絞り込む
+pub fn filter<T>(column: &[T], predicate: impl Fn(&T) -> bool) -> Bitmap {
+    Bitmap::from_fn(column.len(), |row| predicate(&column[row]))
+}
+
+/// 列の各値を変換した新しい列
+pub fn map<T, U>(column: &[T], f: impl Fn(&T) -> U) -> Vec<U> {
+    column.iter().map(f).collect()
+}
+
+/// `mask` に含まれる行の合計
+pub fn sum(column: &[f64], mask: &Bitmap) -> f64 {
+    assert_eq!(column.len(), mask.len(), "column and mask of different lengths");
+    let mut total = 0.0;
+    for (chunk, &word) in column.chunks(64).zip(&mask.words) {
+        match word {
+            0 => {}
+            // 全部含まれるワードは分岐なしで足す
+            u64::MAX => total += chunk.iter().sum::<f64>(),
+            _ => {
+                // 含まれない行は0を足す（行ごとに分岐するより速い）
+                for (bit, value) in chunk.iter().enumerate() {
+                    total += if word >> bit & 1 == 1 { *value } else { 0.0 };
+                }
+            }
+        }
+    }
+    total
+}
+
+/// グループごとの集計
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Group {
+    pub count: usize,
+    pub sum: f64,
+}
+
+/// `mask` に含まれる行を `key(keys[row])` でまとめ、`values` を集計する
+pub fn group_by<T, K: Eq + Hash>(
+    keys: &[T],
+    key: impl Fn(&T) -> K,
+    values: &[f64],
+    mask: &Bitmap,
+) -> HashMap<K, Group> {
+    assert_eq!(keys.len(), values.len(), "columns of different lengths");
+    let mut groups: HashMap<K, Group> = HashMap::new();
+    for row in mask.rows() {
+        let group = groups.entry(key(&keys[row])).or_default();
+        group.count += 1;
+        group.sum += values[row];
+    }
+    groups
+}
+
+/// `Record` の列指向のテーブル
+#[derive(Debug, Default)]
+pub struct RecordTable {
+    ids: Vec<usize>,
+    names: StringArena,
+    values: Vec<f64>,
+}
+
+impl RecordTable {
+    pub fn with_capacity(rows: usize, name_bytes: usize) -> Self {
+        RecordTable {
+            ids: Vec::with_capacity(rows),
+            names: StringArena::with_capacity(rows, name_bytes),
+            values: Vec::with_capacity(rows),
+        }
+    }
+
+    pub fn from_records(records: &[Record]) -> Self {
+        let name_bytes = records.iter().map(|r| r.name.len()).sum();
+        let mut table = RecordTable::with_capacity(records.len(), name_bytes);
+        for record in records {
+            table.push(record.id, &record.name, record.value);
+        }
+        table
+    }
+
+    pub fn push(&mut self, id: usize, name: &str, value: f64) {
+        self.ids.push(id);
+        self.names.push(name);
+        self.values.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn ids(&self) -> &[usize] {
+        &self.ids
+    }
+
+    pub fn names(&self) -> &StringArena {
+        &self.names
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// 名前で絞り込む
+    pub fn filter_names(&self, predicate: impl Fn(&str) -> bool) -> Bitmap {
+        Bitmap::from_fn(self.len(), |row| predicate(self.names.get(row)))
+    }
+
+    /// `row` 行目を `Record` として取り出す
+    pub fn record(&self, row: usize) -> Record {
+        Record::new(self.ids[row], self.names.get(row).to_string(), self.values[row])
+    }
+
+    /// 使っているヒープの大きさ（容量ベース）
+    pub fn heap_bytes(&self) -> usize {
+        self.ids.capacity() * std::mem::size_of::<usize>()
+            + self.values.capacity() * std::mem::size_of::<f64>()
+            + self.names.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(n: usize) -> Vec<Record> {
+        (0..n).map(|i| Record::new(i, format!("Record_{}", i), i as f64 * 1.5)).collect()
+    }
+
+    #[test]
+    fn test_arena_and_round_trip() {
+        let records = records(100);
+        let table = RecordTable::from_records(&records);
+        assert_eq!(table.len(), 100);
+        assert_eq!(table.names().get(0), "Record_0");
+        assert_eq!(table.names().iter().nth(42), Some("Record_42"));
+        let row = table.record(99);
+        assert_eq!((row.id, row.name.as_str(), row.value), (99, "Record_99", 148.5));
+    }
+
+    #[test]
+    fn test_bitmap_operations() {
+        // 64の倍数でない長さで、末尾のビットがはみ出さないこと
+        let even = Bitmap::from_fn(130, |i| i % 2 == 0);
+        let small = Bitmap::from_fn(130, |i| i < 10);
+        assert_eq!(even.count(), 65);
+        assert_eq!(even.not().count(), 65);
+        assert_eq!(Bitmap::full(130).count(), 130);
+        assert_eq!(Bitmap::full(130).not().count(), 0);
+        assert_eq!(even.and(&small).rows().collect::<Vec<_>>(), [0, 2, 4, 6, 8]);
+        assert_eq!(even.or(&small).count(), 70);
+        assert!(even.get(128) && !even.get(129) && !even.get(130));
+    }
+
+    #[test]
+    fn test_operators_match_iterator_pipeline() {
+        let records = records(1000);
+        let table = RecordTable::from_records(&records);
+
+        let even = filter(table.ids(), |id| id % 2 == 0);
+        let expected: f64 = records.iter().filter(|r| r.id % 2 == 0).map(|r| r.value).sum();
+        assert_eq!(sum(table.values(), &even), expected);
+        assert_eq!(sum(table.values(), &Bitmap::full(table.len())), records.iter().map(|r| r.value).sum::<f64>());
+
+        let doubled = map(table.values(), |v| v * 2.0);
+        assert_eq!(sum(&doubled, &even), expected * 2.0);
+
+        let names = table.filter_names(|name| name.ends_with('7'));
+        assert_eq!(names.count(), 100);
+
+        let groups = group_by(table.ids(), |id| id % 3, table.values(), &even);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups.values().map(|g| g.count).sum::<usize>(), 500);
+        let expected_zero: f64 = records.iter().filter(|r| r.id % 6 == 0).map(|r| r.value).sum();
+        assert_eq!(groups[&0].sum, expected_zero);
+    }
+}
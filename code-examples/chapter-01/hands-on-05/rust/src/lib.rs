@@ -0,0 +1,16 @@
+// ハンズオン05の `Record` と、それを列ごとに持つテーブル（`columnar`）
+
+pub mod columnar;
+
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub id: usize,
+    pub name: String,
+    pub value: f64,
+}
+
+impl Record {
+    pub fn new(id: usize, name: String, value: f64) -> Self {
+        Self { id, name, value }
+    }
+}
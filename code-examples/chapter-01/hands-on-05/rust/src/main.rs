@@ -1,18 +1,9 @@
+use bench_harness::Runner;
+use rust_memory_demo::columnar::{self, RecordTable};
+use rust_memory_demo::Record;
+use std::hint::black_box;
 use std::time::Instant;
 
-#[derive(Debug, Clone)]
-struct Record {
-    id: usize,
-    name: String,
-    value: f64,
-}
-
-impl Record {
-    fn new(id: usize, name: String, value: f64) -> Self {
-        Self { id, name, value }
-    }
-}
-
 fn ownership_demo() {
     println!("=== Ownership Demo ===");
     
@@ -71,45 +62,97 @@ fn memory_efficiency_test() {
     println!("Total time: {:.2?}", elapsed);
 }
 
-// ゼロコスト抽象化のデモ
-fn zero_cost_abstraction_demo() {
-    println!("\n=== Zero-Cost Abstraction Demo ===");
-    
-    let records: Vec<Record> = (0..1000000)
-        .map(|i| Record::new(i, format!("Record_{}", i), i as f64 * 1.5))
-        .collect();
-    
-    let start = Instant::now();
-    
-    // 高レベル抽象化だが、高速実行される
-    let result: f64 = records
+// 偶数IDの値の合計（高レベル抽象化だが、高速実行される）
+fn high_level_sum(records: &[Record]) -> f64 {
+    records
         .iter()
         .filter(|r| r.id % 2 == 0)     // 偶数IDのみ
         .map(|r| r.value)              // 値を取得
-        .sum();                        // 合計
+        .sum()                         // 合計
+}
+
+// 同等のローレベル実装
+fn low_level_sum(records: &[Record]) -> f64 {
+    let mut result = 0.0;
+    for record in records {
+        if record.id % 2 == 0 {
+            result += record.value;
+        }
+    }
+    result
+}
+
+fn make_records(count: usize) -> Vec<Record> {
+    (0..count)
+        .map(|i| Record::new(i, format!("Record_{}", i), i as f64 * 1.5))
+        .collect()
+}
+
+// ゼロコスト抽象化のデモ
+fn zero_cost_abstraction_demo(runner: &mut Runner) {
+    println!("\n=== Zero-Cost Abstraction Demo ===");
     
-    let elapsed = start.elapsed();
+    let records = make_records(1_000_000);
     
-    println!("High-level result: {:.2}", result);
-    println!("High-level time: {:.2?}", elapsed);
+    println!("High-level result: {:.2}", high_level_sum(&records));
+    println!("Low-level result: {:.2}", low_level_sum(&records));
     
-    // 同等のローレベル実装
-    let start = Instant::now();
-    let mut low_level_result = 0.0;
-    for record in &records {
-        if record.id % 2 == 0 {
-            low_level_result += record.value;
-        }
+    let high = runner.bench("high_level_iterator", records.len(), || high_level_sum(black_box(&records))).summary.median_ns;
+    let low = runner.bench("low_level_loop", records.len(), || low_level_sum(black_box(&records))).summary.median_ns;
+    println!("Performance difference: {:+.1}% (zero-cost abstraction!)", (high / low - 1.0) * 100.0);
+}
+
+// 同じ集計を列指向のテーブルで行い、`Vec<Record>` のイテレータと比べる
+fn columnar_demo(runner: &mut Runner) {
+    println!("\n=== Columnar Table Demo ===");
+    
+    for rows in [1_000_000, 10_000_000] {
+        let records = make_records(rows);
+        let table = RecordTable::from_records(&records);
+        
+        // Vec<Record> は構造体本体 + 1件ごとの String
+        let row_bytes = records.capacity() * std::mem::size_of::<Record>()
+            + records.iter().map(|r| r.name.capacity()).sum::<usize>();
+        println!("{} rows: Vec<Record> {:.1} MB, RecordTable {:.1} MB",
+            rows,
+            row_bytes as f64 / 1024.0 / 1024.0,
+            table.heap_bytes() as f64 / 1024.0 / 1024.0);
+        
+        let columnar_sum = |table: &RecordTable| {
+            let even = columnar::filter(table.ids(), |id| id % 2 == 0);
+            columnar::sum(table.values(), &even)
+        };
+        assert_eq!(columnar_sum(&table), high_level_sum(&records));
+        
+        let pipeline = runner.bench("iterator_pipeline", rows, || high_level_sum(black_box(&records))).summary.median_ns;
+        let columnar = runner.bench("columnar_filter_sum", rows, || columnar_sum(black_box(&table))).summary.median_ns;
+        println!("Columnar vs iterator pipeline: {:.2}x", pipeline / columnar);
+        
+        // id を10個のバケットに分けた集計
+        runner.bench("columnar_group_by", rows, || {
+            let all = columnar::Bitmap::full(table.len());
+            columnar::group_by(table.ids(), |id| id % 10, table.values(), black_box(&all))
+        });
     }
-    let elapsed = start.elapsed();
     
-    println!("Low-level result: {:.2}", low_level_result);
-    println!("Low-level time: {:.2?}", elapsed);
-    println!("Performance difference: minimal (zero-cost abstraction!)");
+    // ビットマップを組み合わせた絞り込み: 名前が 7 で終わり、値が 1000 以上でない行
+    let table = RecordTable::from_records(&make_records(10_000));
+    let mask = table
+        .filter_names(|name| name.ends_with('7'))
+        .and(&columnar::filter(table.values(), |&v| v >= 1000.0).not());
+    let adjusted = columnar::map(table.values(), |v| v * 1.1);
+    let first = mask.rows().next().map(|row| table.record(row));
+    println!("{} rows match, adjusted sum {:.2}, first {:?}",
+        mask.count(), columnar::sum(&adjusted, &mask), first);
 }
 
 fn main() {
+    let mut runner = Runner::from_args("chapter01-zero-cost");
+    
     ownership_demo();
     memory_efficiency_test();
-    zero_cost_abstraction_demo();
-}
\ No newline at end of file
+    zero_cost_abstraction_demo(&mut runner);
+    columnar_demo(&mut runner);
+    
+    runner.finish();
+}
@@ -25,15 +25,9 @@ fn memory_operations_benchmark(c: &mut Criterion) {
             size,
             |b, &size| {
                 // 事前準備
-                let data: Vec<i32> = (0..size).collect();
+                let data: Vec<i32> = (0..size as i32).collect();
                 
-                b.iter(|| {
-                    let result: i32 = data.iter()
-                        .filter(|&&x| x % 2 == 0)
-                        .map(|&x| x * 2)
-                        .sum();
-                    black_box(result)
-                });
+                b.iter(|| process_data(black_box(&data)));
             }
         );
     }
@@ -1,7 +1,8 @@
 // Library module for performance comparison
-pub fn process_data(data: &[i32]) -> i32 {
+// The sum is kept in 64 bits, as in benchmark-comparison; i32 overflows at 100,000 elements.
+pub fn process_data(data: &[i32]) -> i64 {
     data.iter()
         .filter(|&&x| x % 2 == 0)
-        .map(|&x| x * 2)
+        .map(|&x| x as i64 * 2)
         .sum()
-}
\ No newline at end of file
+}
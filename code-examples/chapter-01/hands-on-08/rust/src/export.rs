@@ -0,0 +1,69 @@
+// スナップショットを memory-profiler に送る
+//
+// memory-profiler は `--alloc-socket <PATH>` で指定したUnixソケットで待ち受け、
+// 1行に1つのJSON（`Snapshot`）を受け取る。
+
+use std::io::{self, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::snapshot;
+
+/// 現在のスナップショットを1つ書き込む
+pub fn write_snapshot(stream: &mut impl Write) -> io::Result<()> {
+    let mut line = serde_json::to_vec(&snapshot())?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()
+}
+
+/// ソケットに接続して、スナップショットを1つだけ送る
+pub fn send_snapshot(path: &Path) -> io::Result<()> {
+    write_snapshot(&mut UnixStream::connect(path)?)
+}
+
+/// `interval` ごとにスナップショットを送り続けるスレッドを起動する
+///
+/// memory-profiler が起動していない、または再起動した場合は、
+/// 同じ間隔で接続し直す。
+pub fn spawn_exporter(path: impl Into<PathBuf>, interval: Duration) -> JoinHandle<()> {
+    let path = path.into();
+    thread::Builder::new()
+        .name("alloc-exporter".to_string())
+        .spawn(move || loop {
+            if let Ok(mut stream) = UnixStream::connect(&path) {
+                while write_snapshot(&mut stream).is_ok() {
+                    thread::sleep(interval);
+                }
+            }
+            thread::sleep(interval);
+        })
+        .expect("failed to spawn exporter thread")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Snapshot;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn test_sends_json_lines() {
+        let path = std::env::temp_dir().join(format!("tracking-allocator-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        send_snapshot(&path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let snapshot: Snapshot = serde_json::from_str(&line).unwrap();
+        assert_eq!(snapshot.pid, std::process::id());
+        assert!(snapshot.stats.allocations > 0);
+    }
+}
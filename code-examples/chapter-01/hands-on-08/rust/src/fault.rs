@@ -0,0 +1,115 @@
+// 割り当ての失敗を意図的に起こす（OOM時の振る舞いのテスト用）
+//
+// 失敗させる条件はスレッドごとに持つので、並行して動く他のテストや
+// ランタイムのスレッドには影響しない。
+
+use std::cell::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    None,
+    // あと何回目の割り当てを失敗させるか（1なら次の割り当て）
+    Nth(u64),
+    // このサイズより大きな割り当てを全て失敗させる
+    Above(usize),
+}
+
+thread_local! {
+    static FAULT: Cell<Fault> = const { Cell::new(Fault::None) };
+}
+
+// 割り当てを失敗させるべきかを判定し、`Nth` の残り回数を進める
+pub(crate) fn should_fail(size: usize) -> bool {
+    FAULT
+        .try_with(|fault| match fault.get() {
+            Fault::None => false,
+            Fault::Nth(1) => {
+                fault.set(Fault::None);
+                true
+            }
+            Fault::Nth(n) => {
+                fault.set(Fault::Nth(n - 1));
+                false
+            }
+            Fault::Above(max) => size > max,
+        })
+        .unwrap_or(false)
+}
+
+/// 現在のスレッドで割り当てを失敗させるガード
+///
+/// 破棄すると、作る前の設定に戻る。失敗した割り当ては `null` を返すので、
+/// `Vec::try_reserve` などの失敗を扱えるAPIで確かめる
+/// （通常の `Vec::push` などではプロセスが中断される）。
+///
+/// ```ignore
+/// let _fault = FaultInjection::fail_above(1 << 20);
+/// assert!(Vec::<u8>::new().try_reserve(2 << 20).is_err());
+/// ```
+pub struct FaultInjection {
+    previous: Fault,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl FaultInjection {
+    fn set(fault: Fault) -> Self {
+        FaultInjection {
+            previous: FAULT.with(|f| f.replace(fault)),
+            _not_send: std::marker::PhantomData,
+        }
+    }
+
+    /// これから `n` 回目（1始まり）の割り当てを1回だけ失敗させる
+    pub fn fail_nth(n: u64) -> Self {
+        assert!(n > 0, "allocations are counted from 1");
+        Self::set(Fault::Nth(n))
+    }
+
+    /// `size` バイトより大きな割り当てを全て失敗させる
+    pub fn fail_above(size: usize) -> Self {
+        Self::set(Fault::Above(size))
+    }
+}
+
+impl Drop for FaultInjection {
+    fn drop(&mut self) {
+        let _ = FAULT.try_with(|f| f.set(self.previous));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fail_nth() {
+        // 判定の途中で他の割り当てが起きないよう、結果は配列に入れる
+        let mut buffers: [Vec<u8>; 3] = Default::default();
+        let _fault = FaultInjection::fail_nth(2);
+        let results = buffers.each_mut().map(|buffer| buffer.try_reserve(64).is_ok());
+        assert_eq!(results, [true, false, true]);
+    }
+
+    #[test]
+    fn test_fail_above_is_restored_on_drop() {
+        {
+            let _fault = FaultInjection::fail_above(1024);
+            assert!(Vec::<u8>::new().try_reserve(512).is_ok());
+            assert!(Vec::<u8>::new().try_reserve(4096).is_err());
+            // 他のスレッドには影響しない
+            let other = std::thread::spawn(|| Vec::<u8>::new().try_reserve(4096).is_ok());
+            assert!(other.join().unwrap());
+        }
+        assert!(Vec::<u8>::new().try_reserve(4096).is_ok());
+    }
+
+    #[test]
+    fn test_realloc_can_fail() {
+        let mut buffer: Vec<u8> = Vec::with_capacity(16);
+        buffer.push(1);
+        let _fault = FaultInjection::fail_above(1024);
+        assert!(buffer.try_reserve(4096).is_err());
+        // 失敗しても元のデータは残る
+        assert_eq!(buffer, vec![1]);
+    }
+}
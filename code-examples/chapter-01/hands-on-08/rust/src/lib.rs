@@ -0,0 +1,306 @@
+//! メモリ割り当てを記録する `GlobalAlloc` のラッパー
+//!
+//! 使う側のバイナリで `#[global_allocator]` に登録する:
+//!
+//! ```ignore
+//! use tracking_allocator::TrackingAllocator;
+//!
+//! #[global_allocator]
+//! static GLOBAL: TrackingAllocator = TrackingAllocator::new();
+//! ```
+//!
+//! 合計だけでなく、生存中のバイト数・ピーク・回数・サイズ別の分布と、
+//! サンプリングした割り当て元のコールスタックを記録する。
+//! `export` でスナップショットを memory-profiler に送れる。
+//! 特定の処理の割り当ては `AllocScope` と `assert_max_allocations!` で測る。
+//! `FaultInjection` で割り当てを意図的に失敗させられる。
+
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+#[cfg(unix)]
+pub mod export;
+mod fault;
+mod scope;
+mod sites;
+
+pub use fault::FaultInjection;
+pub use scope::{AllocScope, ScopeStats};
+pub use sites::{set_sample_every, CallSite};
+
+/// `System` アロケータに処理を任せつつ、割り当てを記録するアロケータ
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    pub const fn new() -> Self {
+        TrackingAllocator
+    }
+}
+
+impl Default for TrackingAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 統計は順序関係を必要としないので、カウンタは全て `Relaxed` で更新する
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static REALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static IN_PLACE_REALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static FAILED_ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+// サイズ別の割り当て回数。`SIZE_CLASS_BOUNDS[i]` バイト以下を i 番目に数え、
+// どれにも入らない大きな割り当ては最後の要素に数える
+const SIZE_CLASS_BOUNDS: [usize; 17] = [
+    8,
+    16,
+    32,
+    64,
+    128,
+    256,
+    512,
+    1 << 10,
+    2 << 10,
+    4 << 10,
+    8 << 10,
+    16 << 10,
+    64 << 10,
+    256 << 10,
+    1 << 20,
+    16 << 20,
+    256 << 20,
+];
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+static SIZE_CLASSES: [AtomicU64; SIZE_CLASS_BOUNDS.len() + 1] = [ZERO; SIZE_CLASS_BOUNDS.len() + 1];
+
+fn size_class(size: usize) -> usize {
+    SIZE_CLASS_BOUNDS
+        .iter()
+        .position(|&bound| size <= bound)
+        .unwrap_or(SIZE_CLASS_BOUNDS.len())
+}
+
+fn record_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    SIZE_CLASSES[size_class(size)].fetch_add(1, Ordering::Relaxed);
+    scope::record_alloc(size);
+    sites::maybe_sample(size);
+}
+
+fn record_dealloc(size: usize) {
+    DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    LIVE_BYTES.fetch_sub(size, Ordering::Relaxed);
+    scope::record_dealloc(size);
+}
+
+// 再割り当ては割り当て回数には含めず、サイズの増減だけを反映する
+fn record_realloc(old_size: usize, new_size: usize, in_place: bool) {
+    REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    if in_place {
+        IN_PLACE_REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    if new_size >= old_size {
+        let growth = new_size - old_size;
+        ALLOCATED_BYTES.fetch_add(growth as u64, Ordering::Relaxed);
+        let live = LIVE_BYTES.fetch_add(growth, Ordering::Relaxed) + growth;
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+        sites::maybe_sample(growth);
+    } else {
+        LIVE_BYTES.fetch_sub(old_size - new_size, Ordering::Relaxed);
+    }
+    scope::record_realloc(old_size, new_size);
+}
+
+fn should_fail(size: usize) -> bool {
+    let fail = fault::should_fail(size);
+    if fail {
+        FAILED_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    fail
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if should_fail(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ret = System.alloc(layout);
+        if !ret.is_null() {
+            record_alloc(layout.size());
+        }
+        ret
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if should_fail(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ret = System.alloc_zeroed(layout);
+        if !ret.is_null() {
+            record_alloc(layout.size());
+        }
+        ret
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    // デフォルトの実装は `alloc` + コピー + `dealloc` になり、再割り当ての回数や
+    // その場での伸長が見えなくなるので、`System` の `realloc` をそのまま使う
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() && should_fail(new_size) {
+            return std::ptr::null_mut();
+        }
+        let ret = System.realloc(ptr, layout, new_size);
+        if !ret.is_null() {
+            record_realloc(layout.size(), new_size, ret == ptr);
+        }
+        ret
+    }
+}
+
+/// 割り当ての累計と現在の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Stats {
+    /// 解放されていないバイト数
+    pub live_bytes: u64,
+    /// `live_bytes` の最大値
+    pub peak_bytes: u64,
+    pub allocations: u64,
+    pub deallocations: u64,
+    /// これまでに割り当てたバイト数の合計（再割り当てによる増加分を含む）
+    pub allocated_bytes: u64,
+    pub reallocations: u64,
+    /// 移動せずにその場でサイズを変えられた再割り当て
+    pub in_place_reallocations: u64,
+    /// `FaultInjection` で失敗させた割り当て
+    pub failed_allocations: u64,
+}
+
+/// 現在のカウンタの値を読む（割り当ては発生しない）
+pub fn stats() -> Stats {
+    Stats {
+        live_bytes: LIVE_BYTES.load(Ordering::Relaxed) as u64,
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed) as u64,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+        allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+        in_place_reallocations: IN_PLACE_REALLOCATIONS.load(Ordering::Relaxed),
+        failed_allocations: FAILED_ALLOCATIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// サイズ別の割り当て回数の1区間
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeClass {
+    /// この区間に入る最大のサイズ（バイト）。`None` は上限なし
+    pub max_size: Option<u64>,
+    pub count: u64,
+}
+
+/// memory-profiler に送るスナップショット
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub pid: u32,
+    /// UNIX時刻（秒）
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub stats: Stats,
+    pub size_classes: Vec<SizeClass>,
+    /// 割り当てたバイト数の多い順に並べた割り当て元
+    pub sites: Vec<CallSite>,
+}
+
+/// スナップショットに含める割り当て元の数
+const TOP_SITES: usize = 20;
+
+/// 現在の統計と、サンプリングした割り当て元を集める
+///
+/// シンボルの解決を行うので、`stats()` よりずっと重い。
+pub fn snapshot() -> Snapshot {
+    let stats = stats();
+    let size_classes = SIZE_CLASSES
+        .iter()
+        .enumerate()
+        .map(|(i, count)| SizeClass {
+            max_size: SIZE_CLASS_BOUNDS.get(i).map(|&bound| bound as u64),
+            count: count.load(Ordering::Relaxed),
+        })
+        .collect();
+    Snapshot {
+        pid: std::process::id(),
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        stats,
+        size_classes,
+        sites: sites::top_sites(TOP_SITES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[global_allocator]
+    static GLOBAL: TrackingAllocator = TrackingAllocator::new();
+
+    #[test]
+    fn test_size_class() {
+        assert_eq!(size_class(0), 0);
+        assert_eq!(size_class(8), 0);
+        assert_eq!(size_class(9), 1);
+        assert_eq!(size_class(1 << 20), 14);
+        assert_eq!(size_class(usize::MAX), SIZE_CLASS_BOUNDS.len());
+    }
+
+    #[test]
+    fn test_tracks_live_and_peak_bytes() {
+        // 他のテストも並行して割り当てるので、増減の下限だけを確かめる
+        let before = stats();
+        let data = vec![0u8; 4 << 20];
+        let during = stats();
+        drop(data);
+        let after = stats();
+
+        assert!(during.allocations > before.allocations);
+        assert!(during.allocated_bytes >= before.allocated_bytes + (4 << 20));
+        assert!(during.peak_bytes >= during.live_bytes);
+        assert!(during.peak_bytes >= 4 << 20);
+        assert!(after.deallocations > before.deallocations);
+
+        let snapshot = snapshot();
+        let large = &snapshot.size_classes[size_class(4 << 20)];
+        assert_eq!(large.max_size, Some(16 << 20));
+        assert!(large.count >= 1);
+        assert_eq!(snapshot.size_classes.last().unwrap().max_size, None);
+    }
+
+    #[test]
+    fn test_tracks_reallocations() {
+        let before = stats();
+        let mut data: Vec<u64> = Vec::with_capacity(1);
+        for i in 0..1000 {
+            data.push(i);
+        }
+        let after = stats();
+
+        // 1回目の確保の後は、全て再割り当てで伸びる
+        assert!(after.reallocations >= before.reallocations + 5);
+        assert!(after.allocated_bytes >= before.allocated_bytes + 8000);
+        assert!(after.in_place_reallocations <= after.reallocations);
+    }
+}
@@ -1,68 +1,92 @@
-use std::alloc::{GlobalAlloc, Layout, System};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::PathBuf;
+use tracking_allocator::{set_sample_every, snapshot, stats, AllocScope, TrackingAllocator};
 
 // カスタムアロケータでメモリ使用量追跡
-struct TrackingAllocator;
-
-static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
-static DEALLOCATED: AtomicUsize = AtomicUsize::new(0);
-
-unsafe impl GlobalAlloc for TrackingAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let ret = System.alloc(layout);
-        if !ret.is_null() {
-            ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
-        }
-        ret
-    }
-    
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        System.dealloc(ptr, layout);
-        DEALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
-    }
-}
-
 #[global_allocator]
-static GLOBAL: TrackingAllocator = TrackingAllocator;
+static GLOBAL: TrackingAllocator = TrackingAllocator::new();
 
-fn get_memory_stats() -> (usize, usize) {
-    (
-        ALLOCATED.load(Ordering::SeqCst),
-        DEALLOCATED.load(Ordering::SeqCst)
-    )
-}
+const USAGE: &str = "\
+Usage: memory_analysis [--sample-every <N>] [--export <SOCKET>]
+
+  --sample-every <N>  record the call stack of every N-th allocation (default: 64, 0 disables)
+  --export <SOCKET>   send a snapshot to memory-profiler's --alloc-socket when done";
 
 fn memory_usage_demo() {
     println!("=== Memory Usage Analysis ===");
-    
-    let (start_alloc, start_dealloc) = get_memory_stats();
-    
+
+    let start = stats();
+
+    // スコープ内の割り当てを、このスレッドの分だけ数える
+    let scope = AllocScope::new("demo");
     {
         // スコープ内でメモリ使用
         let mut data = Vec::with_capacity(100_000);
         for i in 0..100_000 {
             data.push(format!("Record_{}", i));
         }
-        
-        let (peak_alloc, peak_dealloc) = get_memory_stats();
-        let used_memory = peak_alloc - peak_dealloc;
-        println!("Peak memory used: {} bytes ({:.2} MB)", 
-            used_memory, used_memory as f64 / 1024.0 / 1024.0);
-        
+
         // データ処理
         let total_length: usize = data.iter().map(|s| s.len()).sum();
         println!("Total string length: {}", total_length);
-        
+
     } // data がスコープを抜けて自動解放
-    
-    let (end_alloc, end_dealloc) = get_memory_stats();
-    let final_used = end_alloc - end_dealloc;
-    println!("Final memory used: {} bytes", final_used);
-    
-    println!("Memory properly cleaned up: {}", 
-        final_used <= (start_alloc - start_dealloc));
+    let used = scope.finish();
+    println!("Peak memory used: {} bytes ({:.2} MB) in {} allocations",
+        used.peak_bytes, used.peak_bytes as f64 / 1024.0 / 1024.0, used.allocations);
+
+    let end = stats();
+    println!("Final memory used: {} bytes", end.live_bytes);
+    println!("Allocations: {}, reallocations: {} ({} in place), deallocations: {}, peak: {} bytes",
+        end.allocations, end.reallocations, end.in_place_reallocations, end.deallocations, end.peak_bytes);
+
+    println!("Memory properly cleaned up: {}",
+        end.live_bytes <= start.live_bytes);
+}
+
+// サンプリングした割り当て元を、割り当てたバイト数の多い順に表示する
+fn print_call_sites() {
+    let snapshot = snapshot();
+
+    println!("\n=== Allocation Sizes ===");
+    for class in snapshot.size_classes.iter().filter(|c| c.count > 0) {
+        match class.max_size {
+            Some(max) => println!("  <= {:>10} bytes: {}", max, class.count),
+            None => println!("  >  {:>10} bytes: {}", "larger", class.count),
+        }
+    }
+
+    println!("\n=== Top Allocation Sites (estimated) ===");
+    for site in snapshot.sites.iter().take(5) {
+        println!("{} bytes in {} allocations", site.bytes, site.allocations);
+        for frame in site.frames.iter().take(3) {
+            println!("    at {}", frame);
+        }
+    }
 }
 
 fn main() {
+    let mut sample_every = 64;
+    let mut export = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--sample-every", Some(n)) if n.parse::<u64>().is_ok() => sample_every = n.parse().unwrap(),
+            ("--export", Some(path)) => export = Some(PathBuf::from(path)),
+            _ => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+    set_sample_every(sample_every);
+
     memory_usage_demo();
-}
\ No newline at end of file
+    print_call_sites();
+
+    if let Some(path) = export {
+        match tracking_allocator::export::send_snapshot(&path) {
+            Ok(()) => println!("\nSnapshot sent to {}", path.display()),
+            Err(e) => eprintln!("failed to send snapshot to {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// スコープ単位の割り当ての計測
+//
+// グローバルなカウンタの差分では他のスレッドの割り当ても混ざるので、
+// スレッドごとのカウンタを持ち、`AllocScope` はその差分を報告する。
+
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static DEALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static REALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static ALLOCATED_BYTES: Cell<u64> = const { Cell::new(0) };
+    // このスレッドで割り当てたバイト数から解放したバイト数を引いたもの。
+    // 他のスレッドで割り当てたメモリを解放すると負になりうる
+    static LIVE_BYTES: Cell<i64> = const { Cell::new(0) };
+    // 最も内側のスコープが始まってからの `LIVE_BYTES` の最大値
+    static PEAK_BYTES: Cell<i64> = const { Cell::new(0) };
+}
+
+// スレッドの終了処理中は thread_local にアクセスできないので、その間は数えない
+pub(crate) fn record_alloc(size: usize) {
+    let _ = ALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+    let _ = ALLOCATED_BYTES.try_with(|c| c.set(c.get() + size as u64));
+    if let Ok(live) = LIVE_BYTES.try_with(|c| {
+        c.set(c.get() + size as i64);
+        c.get()
+    }) {
+        let _ = PEAK_BYTES.try_with(|c| c.set(c.get().max(live)));
+    }
+}
+
+pub(crate) fn record_dealloc(size: usize) {
+    let _ = DEALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+    let _ = LIVE_BYTES.try_with(|c| c.set(c.get() - size as i64));
+}
+
+pub(crate) fn record_realloc(old_size: usize, new_size: usize) {
+    let _ = REALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+    let growth = new_size as i64 - old_size as i64;
+    let _ = ALLOCATED_BYTES.try_with(|c| c.set(c.get() + growth.max(0) as u64));
+    if let Ok(live) = LIVE_BYTES.try_with(|c| {
+        c.set(c.get() + growth);
+        c.get()
+    }) {
+        let _ = PEAK_BYTES.try_with(|c| c.set(c.get().max(live)));
+    }
+}
+
+/// スコープ内で現在のスレッドが行った割り当て
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScopeStats {
+    pub allocations: u64,
+    pub deallocations: u64,
+    pub reallocations: u64,
+    /// 割り当てたバイト数の合計（再割り当てによる増加分を含む）
+    pub bytes: u64,
+    /// スコープの開始時点から増えた、生存中のバイト数の最大値
+    pub peak_bytes: u64,
+}
+
+/// スコープを抜けるときに、その間の割り当てを報告するガード
+///
+/// ```ignore
+/// let _g = AllocScope::new("parse");
+/// let records = parse(&input);
+/// // `_g` が破棄されるときに標準エラー出力へ報告する
+/// ```
+///
+/// 計測するのは `new` を呼んだスレッドの割り当てだけなので、別のスレッドに
+/// 渡してはいけない（`Send` ではない）。入れ子にもできる。
+pub struct AllocScope {
+    name: &'static str,
+    start: ScopeStats,
+    start_live: i64,
+    // 外側のスコープのピーク。終了時に内側のピークと合わせて戻す
+    outer_peak: i64,
+    report: bool,
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+fn current() -> ScopeStats {
+    ScopeStats {
+        allocations: ALLOCATIONS.with(Cell::get),
+        deallocations: DEALLOCATIONS.with(Cell::get),
+        reallocations: REALLOCATIONS.with(Cell::get),
+        bytes: ALLOCATED_BYTES.with(Cell::get),
+        peak_bytes: 0,
+    }
+}
+
+impl AllocScope {
+    pub fn new(name: &'static str) -> Self {
+        let start_live = LIVE_BYTES.with(Cell::get);
+        let outer_peak = PEAK_BYTES.with(|c| c.replace(start_live));
+        AllocScope {
+            name,
+            start: current(),
+            start_live,
+            outer_peak,
+            report: true,
+            _not_send: std::marker::PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// ここまでの割り当て
+    pub fn stats(&self) -> ScopeStats {
+        let now = current();
+        ScopeStats {
+            allocations: now.allocations - self.start.allocations,
+            deallocations: now.deallocations - self.start.deallocations,
+            reallocations: now.reallocations - self.start.reallocations,
+            bytes: now.bytes - self.start.bytes,
+            peak_bytes: (PEAK_BYTES.with(Cell::get) - self.start_live).max(0) as u64,
+        }
+    }
+
+    /// 報告せずにスコープを終え、結果を返す
+    pub fn finish(mut self) -> ScopeStats {
+        self.report = false;
+        self.stats()
+    }
+}
+
+impl Drop for AllocScope {
+    fn drop(&mut self) {
+        let stats = self.stats();
+        let _ = PEAK_BYTES.try_with(|c| c.set(c.get().max(self.outer_peak)));
+        if self.report {
+            eprintln!(
+                "[alloc] {}: {} allocations, {} reallocations, {} deallocations, {} bytes, peak {} bytes",
+                self.name,
+                stats.allocations,
+                stats.reallocations,
+                stats.deallocations,
+                stats.bytes,
+                stats.peak_bytes
+            );
+        }
+    }
+}
+
+/// 式の評価中に現在のスレッドが行った割り当てが `max` 回以下であることを確かめる
+///
+/// 再割り当て（`Vec` の伸長など）も1回と数える。式の値をそのまま返す。
+///
+/// ```ignore
+/// let total = assert_max_allocations!(0, { values.iter().sum::<u64>() });
+/// ```
+#[macro_export]
+macro_rules! assert_max_allocations {
+    ($max:expr, $body:expr) => {{
+        let scope = $crate::AllocScope::new(concat!(file!(), ":", line!()));
+        let result = $body;
+        let stats = scope.finish();
+        let max: u64 = $max;
+        assert!(
+            stats.allocations + stats.reallocations <= max,
+            "expected at most {} allocations, but {} allocations and {} reallocations were made ({} bytes)",
+            max,
+            stats.allocations,
+            stats.reallocations,
+            stats.bytes
+        );
+        result
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_counts_own_thread_only() {
+        let scope = AllocScope::new("test");
+        let data = vec![0u8; 1000];
+        // 別スレッドの割り当ては数えない
+        std::thread::spawn(|| vec![0u8; 1 << 20]).join().unwrap();
+        drop(data);
+        let stats = scope.finish();
+
+        assert!(stats.allocations >= 1);
+        assert!(stats.bytes >= 1000 && stats.bytes < 1 << 20);
+        assert!(stats.peak_bytes >= 1000 && stats.peak_bytes < 1 << 20);
+        assert!(stats.deallocations >= 1);
+    }
+
+    #[test]
+    fn test_nested_scope_peak() {
+        let outer = AllocScope::new("outer");
+        let big = vec![0u8; 10_000];
+        drop(big);
+        {
+            let inner = AllocScope::new("inner");
+            let small = vec![0u8; 100];
+            drop(small);
+            let stats = inner.finish();
+            assert!(stats.peak_bytes >= 100 && stats.peak_bytes < 10_000);
+        }
+        // 内側のスコープを終えても、外側のピークは失われない
+        assert!(outer.finish().peak_bytes >= 10_000);
+    }
+
+    #[test]
+    fn test_assert_max_allocations() {
+        let values: Vec<u64> = (1..=100).collect();
+        let total = crate::assert_max_allocations!(0, { values.iter().sum::<u64>() });
+        assert_eq!(total, 5050);
+
+        let result = std::panic::catch_unwind(|| {
+            crate::assert_max_allocations!(0, { vec![1, 2, 3] });
+        });
+        assert!(result.is_err());
+
+        // 確保済みのバッファを伸ばす再割り当ても数える
+        let mut buffer = Vec::with_capacity(1);
+        buffer.push(0u8);
+        let result = std::panic::catch_unwind(move || {
+            crate::assert_max_allocations!(0, { buffer.extend_from_slice(&[1; 100]) });
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// 割り当て元のコールスタックのサンプリング
+//
+// 全ての割り当てでスタックを辿ると遅すぎるので、`N` 回に1回だけ記録し、
+// 回数とバイト数は `N` 倍して推定値として数える。
+//
+// アロケータの中で割り当てが起きると（マップへの挿入やシンボル解決など）、
+// 再び記録しようとして無限再帰やデッドロックになる。スレッドごとのフラグで
+// 記録中の割り当ては記録しないようにする。
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// 記録するスタックの深さ。アロケータ内部のフレームを含む
+const MAX_FRAMES: usize = 32;
+
+// 結果に残す、呼び出し側のフレームの数
+const REPORTED_FRAMES: usize = 8;
+
+// 0ならサンプリングしない
+static SAMPLE_EVERY: AtomicU64 = AtomicU64::new(0);
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 命令アドレスの列をキーにした、割り当て元ごとの推定値
+static SITES: Mutex<BTreeMap<[usize; MAX_FRAMES], (u64, u64)>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static BUSY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// `every` 回に1回、割り当て元のコールスタックを記録する（0で無効）
+pub fn set_sample_every(every: u64) {
+    SAMPLE_EVERY.store(every, Ordering::Relaxed);
+}
+
+/// 割り当て元ごとの推定値
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallSite {
+    /// 呼び出し側から順に並べた関数名（アロケータと標準ライブラリのフレームは除く）
+    pub frames: Vec<String>,
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+// 記録中の割り当てを記録しないよう、フラグを立てて `f` を実行する
+// 既に記録中なら `None` を返す
+fn untracked<R>(f: impl FnOnce() -> R) -> Option<R> {
+    // スレッドの終了処理中は thread_local にアクセスできないので、記録しない
+    let entered = BUSY.try_with(|busy| !busy.replace(true)).unwrap_or(false);
+    if !entered {
+        return None;
+    }
+    let result = f();
+    let _ = BUSY.try_with(|busy| busy.set(false));
+    Some(result)
+}
+
+pub(crate) fn maybe_sample(size: usize) {
+    let every = SAMPLE_EVERY.load(Ordering::Relaxed);
+    if every == 0 || !COUNTER.fetch_add(1, Ordering::Relaxed).is_multiple_of(every) {
+        return;
+    }
+    untracked(|| {
+        let mut frames = [0usize; MAX_FRAMES];
+        let mut depth = 0;
+        backtrace::trace(|frame| {
+            frames[depth] = frame.ip() as usize;
+            depth += 1;
+            depth < MAX_FRAMES
+        });
+        let mut sites = SITES.lock().unwrap_or_else(|e| e.into_inner());
+        let site = sites.entry(frames).or_default();
+        site.0 += every;
+        site.1 += size as u64 * every;
+    });
+}
+
+/// 割り当てたバイト数の多い順に `limit` 件の割り当て元を返す
+pub(crate) fn top_sites(limit: usize) -> Vec<CallSite> {
+    untracked(|| {
+        // シンボル解決は遅いので、ロックはコピーする間だけ持つ
+        let sites: Vec<([usize; MAX_FRAMES], (u64, u64))> = {
+            let sites = SITES.lock().unwrap_or_else(|e| e.into_inner());
+            sites.iter().map(|(frames, &counts)| (*frames, counts)).collect()
+        };
+
+        // 呼び出し側のフレームが同じになるスタックはまとめる
+        let mut merged: Vec<CallSite> = Vec::new();
+        for (frames, (allocations, bytes)) in sites {
+            let frames = caller_frames(&frames);
+            match merged.iter_mut().find(|site| site.frames == frames) {
+                Some(site) => {
+                    site.allocations += allocations;
+                    site.bytes += bytes;
+                }
+                None => merged.push(CallSite {
+                    frames,
+                    allocations,
+                    bytes,
+                }),
+            }
+        }
+        merged.sort_by_key(|site| Reverse(site.bytes));
+        merged.truncate(limit);
+        merged
+    })
+    .unwrap_or_default()
+}
+
+// アロケータ・標準ライブラリ・backtrace自身のフレームか
+fn is_internal(name: &str) -> bool {
+    const PREFIXES: [&str; 11] = [
+        "backtrace::",
+        "<tracking_allocator::TrackingAllocator",
+        "tracking_allocator::record_",
+        "tracking_allocator::sites::maybe_sample",
+        "tracking_allocator::sites::untracked",
+        "alloc::",
+        "<alloc::",
+        "core::",
+        "<core::",
+        "std::",
+        "<std::",
+    ];
+    name.starts_with("__rust") || name.starts_with("__rdl") || PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+// 命令アドレスをシンボル名に解決し、先頭の内部フレームを読み飛ばす
+fn caller_frames(frames: &[usize]) -> Vec<String> {
+    frames
+        .iter()
+        .take_while(|&&ip| ip != 0)
+        .map(|&ip| {
+            let mut name = None;
+            backtrace::resolve(ip as *mut std::ffi::c_void, |symbol| {
+                if name.is_none() {
+                    // `{:#}` でハッシュ部分を除いた名前にする
+                    name = symbol.name().map(|n| format!("{:#}", n));
+                }
+            });
+            name.unwrap_or_else(|| format!("{:#x}", ip))
+        })
+        .skip_while(|name| is_internal(name))
+        .take(REPORTED_FRAMES)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline(never)]
+    fn allocate_for_test() -> Vec<Vec<u8>> {
+        (0..64).map(|_| vec![0u8; 256]).collect()
+    }
+
+    #[test]
+    fn test_samples_call_sites() {
+        set_sample_every(1);
+        let data = allocate_for_test();
+        set_sample_every(0);
+        drop(data);
+
+        let sites = top_sites(usize::MAX);
+        let site = sites
+            .iter()
+            .find(|site| site.frames.iter().any(|f| f.contains("allocate_for_test")))
+            .expect("allocate_for_test should be recorded");
+        assert!(site.allocations >= 64);
+        assert!(site.bytes >= 64 * 256);
+        assert!(!is_internal(&site.frames[0]));
+    }
+
+    #[test]
+    fn test_is_internal() {
+        assert!(is_internal("alloc::raw_vec::RawVec<T,A>::grow_one"));
+        assert!(is_internal("<tracking_allocator::TrackingAllocator as core::alloc::global::GlobalAlloc>::alloc"));
+        assert!(!is_internal("memory_analysis::memory_usage_demo"));
+    }
+}
@@ -0,0 +1,285 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::sample::MemDataPoint;
+
+/// アラートの発火条件（いずれもRSSの `memory_kb` に対して判定する）
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// 使用量が `max_kb` を超えた
+    Threshold { max_kb: u64 },
+    /// 直近 `window_secs` 秒の回帰直線の傾きが `min_kb_per_sec` 以上（リークの疑い）
+    Slope { window_secs: u64, min_kb_per_sec: f64 },
+    /// 前回のサンプルから `percent` %以上増えた
+    Spike { percent: f64 },
+}
+
+impl Rule {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Rule::Threshold { .. } => "threshold",
+            Rule::Slope { .. } => "slope",
+            Rule::Spike { .. } => "spike",
+        }
+    }
+}
+
+/// ルールが発火したことを表すイベント
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub timestamp: u64,
+    pub pid: u32,
+    pub name: String,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+// プロセスごとの判定用の状態
+#[derive(Default)]
+struct Series {
+    // 傾きの判定に使う (timestamp, memory_kb)
+    window: VecDeque<(u64, u64)>,
+    previous: Option<u64>,
+    // ルールごとに、条件を満たし続けている間は `true`
+    firing: Vec<bool>,
+}
+
+/// サンプルを受け取ってルールを判定する
+///
+/// 条件を満たし続けている間に毎秒アラートを出さないよう、条件を満たさない
+/// 状態から満たす状態に変わったときだけ発火する。
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<Rule>,
+    // 発火時に `sh -c` で実行するコマンド
+    hook: Option<String>,
+    series: HashMap<u32, Series>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<Rule>, hook: Option<String>) -> Self {
+        AlertEngine {
+            rules,
+            hook,
+            series: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 1回のサンプリング結果を判定し、新たに発火したアラートを返す
+    pub fn evaluate(&mut self, points: &[MemDataPoint]) -> Vec<Alert> {
+        // 終了したプロセスの状態は捨てる（PIDが再利用されても前の値を引き継がない）
+        self.series
+            .retain(|pid, _| points.iter().any(|point| point.pid == *pid));
+
+        let window_secs = self
+            .rules
+            .iter()
+            .filter_map(|rule| match rule {
+                Rule::Slope { window_secs, .. } => Some(*window_secs),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        let mut alerts = Vec::new();
+        for point in points {
+            let series = self.series.entry(point.pid).or_insert_with(|| Series {
+                firing: vec![false; self.rules.len()],
+                ..Default::default()
+            });
+            series.window.push_back((point.timestamp, point.memory_kb));
+            while let Some(&(oldest, _)) = series.window.front() {
+                if oldest + window_secs >= point.timestamp {
+                    break;
+                }
+                series.window.pop_front();
+            }
+
+            for (rule, firing) in self.rules.iter().zip(series.firing.iter_mut()) {
+                let message = check(rule, point, series.previous, &series.window);
+                let was_firing = std::mem::replace(firing, message.is_some());
+                if let (Some(message), false) = (message, was_firing) {
+                    alerts.push(Alert {
+                        timestamp: point.timestamp,
+                        pid: point.pid,
+                        name: point.name.clone(),
+                        rule: rule.kind(),
+                        message,
+                    });
+                }
+            }
+            series.previous = Some(point.memory_kb);
+        }
+        alerts
+    }
+
+    /// アラートをログに出し、フックが設定されていれば実行する
+    pub fn dispatch(&self, alert: &Alert) {
+        tracing::warn!(
+            pid = alert.pid,
+            name = %alert.name,
+            rule = alert.rule,
+            "alert: {}",
+            alert.message
+        );
+        if let Some(hook) = &self.hook {
+            run_hook(hook, alert);
+        }
+    }
+}
+
+// 条件を満たしていれば、その内容を説明するメッセージを返す
+fn check(rule: &Rule, point: &MemDataPoint, previous: Option<u64>, window: &VecDeque<(u64, u64)>) -> Option<String> {
+    match *rule {
+        Rule::Threshold { max_kb } => (point.memory_kb > max_kb)
+            .then(|| format!("memory {} KB exceeds {} KB", point.memory_kb, max_kb)),
+        Rule::Slope {
+            window_secs,
+            min_kb_per_sec,
+        } => {
+            let samples: Vec<(u64, u64)> = window
+                .iter()
+                .copied()
+                .filter(|&(t, _)| t + window_secs >= point.timestamp)
+                .collect();
+            // 窓の半分以上のデータが溜まるまでは判定しない
+            let span = samples.last()?.0 - samples.first()?.0;
+            if samples.len() < 3 || span * 2 < window_secs {
+                return None;
+            }
+            let slope = linear_slope(&samples)?;
+            (slope >= min_kb_per_sec).then(|| {
+                format!(
+                    "memory grew {:.1} KB/s over the last {} s (limit {} KB/s)",
+                    slope, span, min_kb_per_sec
+                )
+            })
+        }
+        Rule::Spike { percent } => {
+            let previous = previous.filter(|&p| p > 0)?;
+            let growth = (point.memory_kb as f64 - previous as f64) / previous as f64 * 100.0;
+            (growth >= percent).then(|| {
+                format!(
+                    "memory jumped {:.0}% from {} KB to {} KB",
+                    growth, previous, point.memory_kb
+                )
+            })
+        }
+    }
+}
+
+/// 最小二乗法による回帰直線の傾き（KB/秒）
+fn linear_slope(samples: &[(u64, u64)]) -> Option<f64> {
+    let n = samples.len() as f64;
+    // 桁落ちを避けるため、最初の時刻からの経過秒で計算する
+    let t0 = samples.first()?.0;
+    let xs = samples.iter().map(|&(t, _)| (t - t0) as f64);
+    let mean_x = xs.clone().sum::<f64>() / n;
+    let mean_y = samples.iter().map(|&(_, kb)| kb as f64).sum::<f64>() / n;
+
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (x, &(_, kb)) in xs.zip(samples) {
+        covariance += (x - mean_x) * (kb as f64 - mean_y);
+        variance += (x - mean_x) * (x - mean_x);
+    }
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+// フックは監視を止めないよう非同期に実行し、アラートの内容を環境変数で渡す
+fn run_hook(command: &str, alert: &Alert) {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("ALERT_RULE", alert.rule)
+        .env("ALERT_PID", alert.pid.to_string())
+        .env("ALERT_NAME", &alert.name)
+        .env("ALERT_TIMESTAMP", alert.timestamp.to_string())
+        .env("ALERT_MESSAGE", &alert.message)
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => tracing::warn!("alert hook exited with {}", status),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("failed to wait for alert hook: {}", e),
+                }
+            });
+        }
+        Err(e) => tracing::error!("failed to run alert hook: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, memory_kb: u64) -> MemDataPoint {
+        MemDataPoint {
+            timestamp,
+            pid: 42,
+            name: "app".to_string(),
+            memory_kb,
+            ..Default::default()
+        }
+    }
+
+    // 1秒ごとのサンプル列を流し、発火したアラートの (時刻, ルール) を返す
+    fn run(rules: Vec<Rule>, series: &[u64]) -> Vec<(u64, &'static str)> {
+        let mut engine = AlertEngine::new(rules, None);
+        series
+            .iter()
+            .enumerate()
+            .flat_map(|(t, &kb)| engine.evaluate(&[point(t as u64, kb)]))
+            .map(|alert| (alert.timestamp, alert.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_threshold_fires_once_per_crossing() {
+        let rules = vec![Rule::Threshold { max_kb: 100 }];
+        let fired = run(rules, &[90, 110, 120, 95, 130]);
+        assert_eq!(fired, vec![(1, "threshold"), (4, "threshold")]);
+    }
+
+    #[test]
+    fn test_slope_detects_steady_growth() {
+        let rules = vec![Rule::Slope {
+            window_secs: 10,
+            min_kb_per_sec: 5.0,
+        }];
+        // ノイズはあるが平坦な区間では発火しない
+        let flat: Vec<u64> = (0..30).map(|t| 1000 + (t % 3) * 20).collect();
+        assert!(run(rules.clone(), &flat).is_empty());
+
+        // 毎秒8KBずつ増え続けると、窓の半分のデータが溜まった時点で発火する
+        let leak: Vec<u64> = (0..30).map(|t| 1000 + t * 8).collect();
+        assert_eq!(run(rules, &leak), vec![(5, "slope")]);
+    }
+
+    #[test]
+    fn test_spike_compares_with_previous_sample() {
+        let rules = vec![Rule::Spike { percent: 50.0 }];
+        let fired = run(rules, &[100, 120, 200, 210, 100, 400]);
+        assert_eq!(fired, vec![(2, "spike"), (5, "spike")]);
+    }
+
+    #[test]
+    fn test_state_is_dropped_when_process_exits() {
+        let mut engine = AlertEngine::new(vec![Rule::Spike { percent: 50.0 }], None);
+        assert!(engine.evaluate(&[point(0, 100)]).is_empty());
+        assert!(engine.evaluate(&[]).is_empty());
+        // 前回の値が残っていれば急増と判定されるが、新しいプロセスとして扱う
+        assert!(engine.evaluate(&[point(2, 1000)]).is_empty());
+    }
+
+    #[test]
+    fn test_linear_slope() {
+        assert_eq!(linear_slope(&[(10, 0), (11, 2), (12, 4)]), Some(2.0));
+        assert_eq!(linear_slope(&[(10, 5)]), None);
+    }
+}
@@ -0,0 +1,201 @@
+// 対象プロセス内の `TrackingAllocator`（chapter-01/hands-on-08）から送られる
+// 割り当てのスナップショットを受け取る
+//
+// プロトコルはUnixソケット上の1行1JSON。外から見たRSSだけでは分からない
+// 「どこで割り当てたか」を、対象プロセス自身に報告してもらう。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/// サイズ別の割り当て回数の1区間（`max_size` が `None` なら上限なし）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeClass {
+    pub max_size: Option<u64>,
+    pub count: u64,
+}
+
+/// サンプリングから推定した、割り当て元ごとの回数とバイト数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallSite {
+    pub frames: Vec<String>,
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+/// `tracking_allocator::Snapshot` と同じ形のスナップショット
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AllocSnapshot {
+    pub pid: u32,
+    pub timestamp: u64,
+    pub live_bytes: u64,
+    pub peak_bytes: u64,
+    pub allocations: u64,
+    pub deallocations: u64,
+    pub allocated_bytes: u64,
+    #[serde(default)]
+    pub reallocations: u64,
+    #[serde(default)]
+    pub in_place_reallocations: u64,
+    #[serde(default)]
+    pub failed_allocations: u64,
+    #[serde(default)]
+    pub size_classes: Vec<SizeClass>,
+    #[serde(default)]
+    pub sites: Vec<CallSite>,
+}
+
+/// プロセスごとの最新のスナップショット
+#[derive(Default)]
+pub struct AllocStore {
+    latest: Mutex<BTreeMap<u32, AllocSnapshot>>,
+}
+
+impl AllocStore {
+    pub fn update(&self, snapshot: AllocSnapshot) {
+        self.latest.lock().unwrap().insert(snapshot.pid, snapshot);
+    }
+
+    /// PID順の最新のスナップショット
+    pub fn latest(&self) -> Vec<AllocSnapshot> {
+        self.latest.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// `path` で待ち受け、接続してきたプロセスのスナップショットを `store` に入れる
+pub async fn listen(path: &Path, store: Arc<AllocStore>) -> std::io::Result<()> {
+    // 前回の実行で残ったソケットファイルがあると bind に失敗する
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    tracing::info!("receiving allocation snapshots on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(receive(stream, store.clone()));
+    }
+}
+
+async fn receive(stream: UnixStream, store: Arc<AllocStore>) {
+    let mut lines = BufReader::new(stream).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => match serde_json::from_str::<AllocSnapshot>(&line) {
+                Ok(snapshot) => {
+                    tracing::debug!(
+                        "allocation snapshot from {}: {} live bytes, {} sites",
+                        snapshot.pid,
+                        snapshot.live_bytes,
+                        snapshot.sites.len()
+                    );
+                    store.update(snapshot);
+                }
+                Err(e) => tracing::warn!("skipping malformed allocation snapshot: {}", e),
+            },
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("allocation snapshot connection failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tokio::io::AsyncWriteExt;
+
+    // CIが混んでいても落ちないよう、固定の時間ではなく期限まで繰り返し確かめる
+    const DEADLINE: Duration = Duration::from_secs(2);
+
+    async fn poll<T>(mut f: impl FnMut() -> Option<T>) -> T {
+        let start = Instant::now();
+        loop {
+            if let Some(value) = f() {
+                return value;
+            }
+            assert!(start.elapsed() < DEADLINE, "timed out after {:?}", DEADLINE);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_receives_snapshots() {
+        let path = std::env::temp_dir().join(format!("memory-profiler-alloc-{}.sock", std::process::id()));
+        let store = Arc::new(AllocStore::default());
+        let server = tokio::spawn({
+            let (path, store) = (path.clone(), store.clone());
+            async move { listen(&path, store).await }
+        });
+
+        // 待ち受けを始めるまでは接続に失敗する
+        let start = Instant::now();
+        let mut client = loop {
+            match UnixStream::connect(&path).await {
+                Ok(client) => break client,
+                Err(e) if start.elapsed() >= DEADLINE => panic!("failed to connect: {}", e),
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        client
+            .write_all(
+                b"not json\n\
+                  {\"pid\":7,\"timestamp\":100,\"live_bytes\":10,\"peak_bytes\":20,\"allocations\":3,\
+                  \"deallocations\":1,\"allocated_bytes\":30,\
+                  \"sites\":[{\"frames\":[\"app::parse\"],\"allocations\":3,\"bytes\":30}]}\n",
+            )
+            .await
+            .unwrap();
+        drop(client);
+        let latest = poll(|| Some(store.latest()).filter(|latest| !latest.is_empty())).await;
+        server.abort();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].pid, 7);
+        assert_eq!(latest[0].sites[0].frames, vec!["app::parse"]);
+    }
+
+    #[test]
+    fn test_matches_tracking_allocator_format() {
+        use tracking_allocator::{CallSite as SentSite, SizeClass as SentClass, Snapshot, Stats};
+
+        // すべてのフィールドに0でない値を入れて、取りこぼしがあれば分かるようにする
+        let sent = Snapshot {
+            pid: 42,
+            timestamp: 1_700_000_000,
+            stats: Stats {
+                live_bytes: 1,
+                peak_bytes: 2,
+                allocations: 3,
+                deallocations: 4,
+                allocated_bytes: 5,
+                reallocations: 6,
+                in_place_reallocations: 7,
+                failed_allocations: 8,
+            },
+            size_classes: vec![
+                SentClass { max_size: Some(64), count: 9 },
+                SentClass { max_size: None, count: 10 },
+            ],
+            sites: vec![SentSite {
+                frames: vec!["app::main".to_string()],
+                allocations: 11,
+                bytes: 12,
+            }],
+        };
+        let encoded = serde_json::to_value(&sent).unwrap();
+        let received: AllocSnapshot = serde_json::from_value(encoded.clone()).unwrap();
+        // 受け取った側で読み落としたフィールドがあれば、書き戻したときに一致しない
+        assert_eq!(serde_json::to_value(&received).unwrap(), encoded);
+
+        // 実際の送信処理が書く行も読める
+        let mut line = Vec::new();
+        tracking_allocator::export::write_snapshot(&mut line).unwrap();
+        let received: AllocSnapshot = serde_json::from_slice(&line).unwrap();
+        assert_eq!(received.pid, std::process::id());
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+use crate::sample::MemDataPoint;
+
+/// サンプルを追記専用のCSVファイルに記録する
+///
+/// 既存のファイルに追記する場合はヘッダ行を書かないので、同じファイルに
+/// 複数回の記録を続けて残せる。読み出しはヘッダ名で列を対応付ける。
+pub struct HistoryWriter {
+    writer: csv::Writer<File>,
+}
+
+impl HistoryWriter {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let is_empty = file.metadata()?.len() == 0;
+        let writer = csv::WriterBuilder::new()
+            .has_headers(is_empty)
+            .from_writer(file);
+        Ok(HistoryWriter { writer })
+    }
+
+    pub fn append(&mut self, point: &MemDataPoint) -> csv::Result<()> {
+        self.writer.serialize(point)?;
+        // プロセスが落ちても記録が残るよう、1サンプルごとに書き出す
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// `[from, to]` の範囲（両端を含む）のサンプルを読み出す
+pub fn read_range(path: &Path, from: Option<u64>, to: Option<u64>) -> csv::Result<Vec<MemDataPoint>> {
+    // 列が追加される前に書かれた行と混在していても読めるよう、列数の違いを許す
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let mut points = Vec::new();
+    for record in reader.deserialize::<MemDataPoint>() {
+        let point = match record {
+            Ok(point) => point,
+            // 書き込み途中の最終行などは読み飛ばす
+            Err(e) => {
+                tracing::warn!("skipping malformed history record: {}", e);
+                continue;
+            }
+        };
+        if from.is_some_and(|from| point.timestamp < from) || to.is_some_and(|to| point.timestamp > to) {
+            continue;
+        }
+        points.push(point);
+    }
+    Ok(points)
+}
+
+/// プロセスごとに `step` 秒のバケットに分けて、点数を減らす
+///
+/// 各バケットの代表点は、RSSをバケット内の平均にした最後のサンプルで、
+/// タイムスタンプはバケットの開始時刻になる。その他の指標は最後のサンプルの値を使う。
+/// `step` が1以下なら元のサンプルをそのまま返す。
+pub fn downsample(points: &[MemDataPoint], step: u64) -> Vec<MemDataPoint> {
+    if step <= 1 {
+        return points.to_vec();
+    }
+    // (バケット開始時刻, PID) → (最後のサンプル, RSSの合計, 個数)
+    let mut buckets: BTreeMap<(u64, u32), (&MemDataPoint, u64, u64)> = BTreeMap::new();
+    for point in points {
+        let key = (point.timestamp - point.timestamp % step, point.pid);
+        let bucket = buckets.entry(key).or_insert((point, 0, 0));
+        bucket.0 = point;
+        bucket.1 += point.memory_kb;
+        bucket.2 += 1;
+    }
+    buckets
+        .into_iter()
+        .map(|((timestamp, _), (last, sum, count))| MemDataPoint {
+            timestamp,
+            memory_kb: sum / count,
+            ..last.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64, memory_kb: u64) -> MemDataPoint {
+        MemDataPoint {
+            timestamp,
+            pid: 42,
+            name: "app".to_string(),
+            memory_kb,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_append_and_read_range() {
+        let path = std::env::temp_dir().join(format!("memory-profiler-history-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // 2回に分けて開いても、ヘッダは1回だけ書かれる
+        let mut writer = HistoryWriter::open(&path).unwrap();
+        writer.append(&point(100, 10)).unwrap();
+        writer.append(&point(101, 20)).unwrap();
+        drop(writer);
+        let mut writer = HistoryWriter::open(&path).unwrap();
+        writer.append(&point(102, 30)).unwrap();
+        drop(writer);
+
+        let all = read_range(&path, None, None).unwrap();
+        let range = read_range(&path, Some(101), Some(102)).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(all.len(), 3);
+        assert_eq!(range, vec![point(101, 20), point(102, 30)]);
+        assert_eq!(contents.matches("timestamp").count(), 1);
+    }
+
+    #[test]
+    fn test_downsample_averages_buckets() {
+        let points = vec![point(100, 10), point(104, 30), point(110, 50), point(119, 70)];
+        assert_eq!(downsample(&points, 10), vec![point(100, 20), point(110, 60)]);
+        assert_eq!(downsample(&points, 0), points);
+
+        // プロセスごとに別々に平均する
+        let mut other = point(105, 1000);
+        other.pid = 7;
+        let mixed = vec![point(100, 10), other.clone(), point(104, 30)];
+        let mut expected_other = other;
+        expected_other.timestamp = 100;
+        assert_eq!(downsample(&mixed, 10), vec![expected_other, point(100, 20)]);
+    }
+
+    #[test]
+    fn test_reads_files_without_pid_columns() {
+        let path = std::env::temp_dir().join(format!("memory-profiler-legacy-{}.csv", std::process::id()));
+        std::fs::write(&path, "timestamp,memory_kb\n100,10\n").unwrap();
+        let points = read_range(&path, None, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!((points[0].pid, points[0].memory_kb), (0, 10));
+    }
+}
@@ -1,20 +1,180 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
+    http::{header, StatusCode},
     response::IntoResponse,
     routing::get,
-    Router,
+    Json, Router,
 };
-use std::{net::SocketAddr, sync::Arc};
-use sysinfo::{System, SystemExt, ProcessExt, Pid};
-use tokio::time::{interval, Duration};
+use serde::Deserialize;
+use std::{net::SocketAddr, path::{Path, PathBuf}, sync::Arc};
+use sysinfo::Pid;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{sleep, Duration};
 use tower_http::services::ServeDir;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod alert;
+mod alloc;
+mod history;
+mod metrics;
+mod procfs;
+mod sample;
+mod sampler;
+mod target;
+
+use alert::{Alert, AlertEngine, Rule};
+use alloc::{AllocSnapshot, AllocStore};
+use history::HistoryWriter;
+use sample::MemDataPoint;
+use sampler::{Batch, Sampler, Throttle};
+use target::TargetSpec;
+
+const USAGE: &str = "\
+Usage: memory-profiler [PID]... [--tree] [--name <PATTERN>]... [--record <FILE>] [ALERT OPTIONS]
+                       [--alloc-socket <PATH>]
+       memory-profiler --replay <FILE>
+
+  --tree                 also monitor all child processes of the targets
+  --name <PATTERN>       attach to processes whose name matches PATTERN (`*` wildcard)
+  --record <FILE>        append samples to FILE (CSV)
+  --replay <FILE>        serve a recorded session instead of live processes
+  --alloc-socket <PATH>  receive allocation snapshots from TrackingAllocator on a Unix socket
+
+Alert options:
+  --alert-max <KB>       alert when memory exceeds KB
+  --alert-slope <KB/S>   alert when memory keeps growing faster than KB/S
+  --alert-window <SECS>  regression window for --alert-slope, at least 1 (default: 300)
+  --alert-spike <PCT>    alert when memory grows PCT% between two samples
+  --alert-hook <CMD>     run CMD via `sh -c` on each alert (ALERT_* env vars)";
+
+// リプレイ時、記録の間隔がこれより空いていても待つ時間はこれで打ち切る
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(5);
+
+// `/ws?speed=` で受け付ける再生速度の範囲。範囲外は端に丸める
+// （極端に小さいと待ち時間が `Duration` に収まらない）
+const MIN_REPLAY_SPEED: f64 = 0.01;
+const MAX_REPLAY_SPEED: f64 = 1000.0;
+
+// 共有サンプラーの走査間隔。クライアントはこの倍数の間隔で購読できる
+const SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+
+// `/ws?interval=` で受け付ける送信間隔の上限（秒）
+const MAX_LIVE_INTERVAL: u64 = 3600;
+
+// 新しく接続したダッシュボードに送り直す直近のバッチ数（120点 = 2分）
+const BACKFILL_BATCHES: usize = 120;
+
+// `--alert-slope` の回帰に使うデフォルトの期間（秒）
+const DEFAULT_SLOPE_WINDOW: u64 = 300;
+
+enum Mode {
+    // 実行中のプロセスを監視する
+    Live { sampler: Arc<Sampler> },
+    // 記録済みのセッションを再生する（監視対象のプロセスは不要）
+    Replay,
+}
+
 struct AppState {
-    pid: Pid,
+    mode: Mode,
+    // 記録先（ライブ時）または再生元（リプレイ時）の履歴ファイル
+    history_path: Option<PathBuf>,
+    // `TrackingAllocator` からスナップショットを受け取るソケット
+    alloc_socket: Option<PathBuf>,
+    allocations: Arc<AllocStore>,
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("{} requires a number", option))
+}
+
+fn parse_args() -> Result<(AppState, AlertEngine, Option<HistoryWriter>), String> {
+    let mut targets = TargetSpec::default();
+    let mut record = None;
+    let mut replay = None;
+    let mut rules = Vec::new();
+    let mut slope = None;
+    let mut window_secs = None;
+    let mut hook = None;
+    let mut alloc_socket = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--alert-max" => rules.push(Rule::Threshold {
+                max_kb: parse_number(&arg, args.next())?,
+            }),
+            "--alert-slope" => slope = Some(parse_number(&arg, args.next())?),
+            "--alert-window" => {
+                // 0秒の窓には点が1つしか入らず、傾きを求められない
+                let secs: u64 = parse_number(&arg, args.next())?;
+                if secs == 0 {
+                    return Err("--alert-window must be at least 1 second".to_string());
+                }
+                window_secs = Some(secs);
+            }
+            "--alert-spike" => rules.push(Rule::Spike {
+                percent: parse_number(&arg, args.next())?,
+            }),
+            "--alert-hook" => hook = Some(args.next().ok_or("--alert-hook requires a command")?),
+            "--tree" => targets.include_children = true,
+            "--name" => targets
+                .name_patterns
+                .push(args.next().ok_or("--name requires a pattern")?),
+            "--alloc-socket" => {
+                alloc_socket = Some(PathBuf::from(args.next().ok_or("--alloc-socket requires a path")?))
+            }
+            "--record" => record = Some(args.next().ok_or("--record requires a file")?),
+            "--replay" => replay = Some(args.next().ok_or("--replay requires a file")?),
+            _ => {
+                let parsed = arg.parse::<usize>().map_err(|_| "PID must be a number")?;
+                targets.pids.push(Pid::from(parsed));
+            }
+        }
+    }
+
+    match (slope, window_secs) {
+        (Some(min_kb_per_sec), window_secs) => rules.push(Rule::Slope {
+            window_secs: window_secs.unwrap_or(DEFAULT_SLOPE_WINDOW),
+            min_kb_per_sec,
+        }),
+        (None, Some(_)) => return Err("--alert-window requires --alert-slope".to_string()),
+        (None, None) => {}
+    }
+    let alerts = AlertEngine::new(rules, hook);
+    // 記録先は起動前に開いておき、開けなければ他の引数の誤りと同じく使い方を表示して終わる
+    let writer = match (&record, &replay) {
+        (Some(path), None) => Some(
+            HistoryWriter::open(Path::new(path))
+                .map_err(|e| format!("failed to open history file {}: {}", path, e))?,
+        ),
+        _ => None,
+    };
+
+    let state = match (targets.is_empty(), record, replay) {
+        (false, record, None) => AppState {
+            mode: Mode::Live {
+                sampler: Sampler::new(targets, BACKFILL_BATCHES),
+            },
+            history_path: record.map(PathBuf::from),
+            alloc_socket,
+            allocations: Arc::default(),
+        },
+        (true, None, Some(_)) if !alerts.is_empty() => {
+            return Err("alerts are only available when monitoring live processes".to_string())
+        }
+        (true, None, Some(replay)) => AppState {
+            mode: Mode::Replay,
+            history_path: Some(PathBuf::from(replay)),
+            alloc_socket,
+            allocations: Arc::default(),
+        },
+        _ => return Err("either target processes or --replay <FILE> is required".to_string()),
+    };
+    Ok((state, alerts, writer))
 }
 
 #[tokio::main]
@@ -27,19 +187,40 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let pid_str = std::env::args()
-        .nth(1)
-        .expect("Usage: memory-profiler <PID>");
-    let pid = pid_str.parse::<usize>()
-        .expect("PID must be a number")
-        .into();
+    let (app_state, alerts, writer) = match parse_args() {
+        Ok((state, alerts, writer)) => (Arc::new(state), alerts, writer),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Mode::Live { sampler } = &app_state.mode {
+        if let (Some(path), Some(writer)) = (&app_state.history_path, writer) {
+            tracing::info!("recording samples to {}", path.display());
+            // 最初のバッチを取りこぼさないよう、サンプラーより先に購読しておく
+            let (_, receiver) = sampler.subscribe();
+            tokio::spawn(record_samples(sampler.clone(), receiver, writer));
+        }
+        tokio::spawn(sampler.clone().run(SAMPLE_PERIOD, alerts));
+    }
 
-    let app_state = Arc::new(AppState { pid });
+    if let Some(path) = app_state.alloc_socket.clone() {
+        let store = app_state.allocations.clone();
+        tokio::spawn(async move {
+            if let Err(e) = alloc::listen(&path, store).await {
+                tracing::error!("failed to listen on {}: {}", path.display(), e);
+            }
+        });
+    }
 
     // `static`ディレクトリ以下のファイルを静的に配信する
     let app = Router::new()
-        .nest_service("/", ServeDir::new("static"))
         .route("/ws", get(websocket_handler))
+        .route("/history", get(history_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/allocations", get(allocations_handler))
+        .fallback_service(ServeDir::new("static"))
         .with_state(app_state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -49,52 +230,201 @@ async fn main() {
         .unwrap();
 }
 
+// ダッシュボードの接続の有無に関係なく、共有サンプラーのバッチを全て履歴ファイルに追記する
+async fn record_samples(
+    sampler: Arc<Sampler>,
+    mut receiver: tokio::sync::broadcast::Receiver<Arc<Batch>>,
+    mut writer: HistoryWriter,
+) {
+    loop {
+        let batch = match receiver.recv().await {
+            Ok(batch) => batch,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("recorder lagged behind; {} batches were not recorded", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        for data_point in &batch.points {
+            if let Err(e) = writer.append(data_point) {
+                tracing::error!("failed to record sample: {}", e);
+            }
+        }
+        if sampler.is_final(&batch) {
+            tracing::info!("Target processes not found. Stopping recording.");
+            break;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+    // ダウンサンプリングの間隔（秒）
+    step: Option<u64>,
+}
+
+// 記録済みのサンプルを時間範囲で取り出す
+// 例: `/history?from=1700000000&to=1700003600&step=60`
+async fn history_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<MemDataPoint>>, (StatusCode, String)> {
+    let path = state
+        .history_path
+        .clone()
+        .ok_or((StatusCode::NOT_FOUND, "no history file (start with --record)".to_string()))?;
+
+    let points = tokio::task::spawn_blocking(move || history::read_range(&path, query.from, query.to))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(history::downsample(&points, query.step.unwrap_or(0))))
+}
+
+// 最新のサンプルをPrometheusなどからスクレイプできるよう、OpenMetrics形式で返す
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let Mode::Live { ref sampler } = state.mode else {
+        return Err((StatusCode::NOT_FOUND, "metrics are only available when monitoring live processes"));
+    };
+    let latest = sampler.latest();
+    let points = latest.as_ref().map_or(&[][..], |batch| &batch.points);
+    let body = metrics::render(points, &sampler.latency());
+    Ok(([(header::CONTENT_TYPE, metrics::CONTENT_TYPE)], body))
+}
+
+// `--alloc-socket` で受け取った、プロセスごとの最新の割り当てのスナップショット
+async fn allocations_handler(State(state): State<Arc<AppState>>) -> Json<Vec<AllocSnapshot>> {
+    Json(state.allocations.latest())
+}
+
+#[derive(Deserialize)]
+struct WsQuery {
+    // ライブ時の送信間隔（秒、1〜3600）。デフォルトはサンプラーと同じ1秒
+    interval: Option<u64>,
+    // リプレイ時の再生速度の倍率（デフォルトは等速）
+    speed: Option<f64>,
+}
+
+// 例: `/ws?interval=5`（ライブ）、`/ws?speed=10`（リプレイ、0.01〜1000倍）
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    Query(query): Query<WsQuery>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| async move {
+        match state.mode {
+            Mode::Live { ref sampler } => {
+                let interval = query.interval.unwrap_or(1).clamp(1, MAX_LIVE_INTERVAL);
+                handle_socket(socket, sampler, Throttle::new(interval)).await
+            }
+            Mode::Replay => {
+                let speed = query
+                    .speed
+                    .filter(|s| *s > 0.0)
+                    .map_or(1.0, |s| s.clamp(MIN_REPLAY_SPEED, MAX_REPLAY_SPEED));
+                replay_socket(socket, state, speed).await
+            }
+        }
+    })
 }
 
-#[derive(serde::Serialize)]
-struct MemDataPoint {
-    timestamp: u64,
-    memory_kb: u64,
+async fn send_point(socket: &mut WebSocket, data_point: &MemDataPoint) -> bool {
+    let json_payload = serde_json::to_string(data_point).unwrap();
+    socket.send(Message::Text(json_payload)).await.is_ok()
 }
 
-async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
-    let mut sys = System::new_all();
-    let mut interval = interval(Duration::from_secs(1));
+// アラートはサンプルと区別できるよう `{"alert": {...}}` の形で送る
+async fn send_alert(socket: &mut WebSocket, alert: &Alert) -> bool {
+    let json_payload = serde_json::json!({ "alert": alert }).to_string();
+    socket.send(Message::Text(json_payload)).await.is_ok()
+}
 
-    loop {
-        interval.tick().await;
-        sys.refresh_process(state.pid);
+// アラートは送信間隔で間引かず、全てのクライアントに必ず送る
+async fn send_batch(socket: &mut WebSocket, batch: &Batch, admitted: bool) -> bool {
+    for alert in &batch.alerts {
+        if !send_alert(socket, alert).await {
+            return false;
+        }
+    }
+    if !admitted {
+        return true;
+    }
+    for data_point in &batch.points {
+        if !send_point(socket, data_point).await {
+            return false;
+        }
+    }
+    true
+}
 
-        let mem_kb = if let Some(process) = sys.process(state.pid) {
-            process.memory()
-        } else {
-            0
-        };
+async fn handle_socket(mut socket: WebSocket, sampler: &Sampler, mut throttle: Throttle) {
+    // 接続直後に直近の履歴を送り、グラフが空の状態から始まらないようにする
+    let (backfill, mut receiver) = sampler.subscribe();
+    for batch in &backfill {
+        if !send_batch(&mut socket, batch, throttle.admit(batch)).await {
+            tracing::debug!("Client disconnected.");
+            return;
+        }
+    }
+    if sampler.is_finished() {
+        let _ = socket.close().await;
+        return;
+    }
 
-        let data_point = MemDataPoint {
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            memory_kb: mem_kb,
+    loop {
+        let batch = match receiver.recv().await {
+            Ok(batch) => batch,
+            // 遅いクライアントは古いバッチを読み飛ばして追いつく
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
         };
 
-        let json_payload = serde_json::to_string(&data_point).unwrap();
-
-        if socket.send(Message::Text(json_payload)).await.is_err() {
+        if !send_batch(&mut socket, &batch, throttle.admit(&batch)).await {
             tracing::debug!("Client disconnected.");
-            break;
+            return;
         }
 
-        if mem_kb == 0 {
-            tracing::debug!("Target process not found. Closing connection.");
-            let _ = socket.close().await;
+        if sampler.is_final(&batch) {
+            tracing::debug!("Target processes not found. Closing connection.");
             break;
         }
     }
+    let _ = socket.close().await;
+}
+
+// 記録されたセッションを、記録時の間隔（を `speed` 倍したもの）で送り直す
+async fn replay_socket(mut socket: WebSocket, state: Arc<AppState>, speed: f64) {
+    let Some(path) = state.history_path.clone() else {
+        return;
+    };
+    let points = match tokio::task::spawn_blocking(move || history::read_range(&path, None, None)).await {
+        Ok(Ok(points)) => points,
+        Ok(Err(e)) => {
+            tracing::error!("failed to read history: {}", e);
+            return;
+        }
+        Err(e) => {
+            tracing::error!("failed to read history: {}", e);
+            return;
+        }
+    };
+
+    let mut previous: Option<u64> = None;
+    for data_point in &points {
+        if let Some(previous) = previous {
+            let gap = Duration::from_secs(data_point.timestamp.saturating_sub(previous));
+            sleep(gap.min(MAX_REPLAY_GAP).div_f64(speed)).await;
+        }
+        previous = Some(data_point.timestamp);
+
+        if !send_point(&mut socket, data_point).await {
+            tracing::debug!("Client disconnected.");
+            return;
+        }
+    }
+    tracing::debug!("Replay finished.");
+    let _ = socket.close().await;
 }
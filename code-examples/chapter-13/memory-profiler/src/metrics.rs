@@ -0,0 +1,154 @@
+// `/metrics` で公開するOpenMetricsテキスト形式の出力
+//
+// 形式は単純なので、クライアントライブラリは使わずに直接組み立てる。
+// 仕様: https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+
+use std::fmt::Write;
+
+use crate::sample::MemDataPoint;
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// サンプリング1回にかかった時間のバケットの上限（秒）
+const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// プロファイラ自身のサンプリング時間の分布
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    // バケットごとの（累積でない）件数。最後の要素は +Inf
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, seconds: f64) {
+        let index = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[index] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+// PIDごとのゲージ: (名前, 単位, 説明, 値の取り出し方)
+type Gauge = (&'static str, &'static str, &'static str, fn(&MemDataPoint) -> Option<f64>);
+
+const PROCESS_GAUGES: [Gauge; 8] = [
+    ("memory_profiler_resident_memory_bytes", "bytes", "Resident set size of the process.", |p| {
+        Some(p.memory_kb as f64 * 1024.0)
+    }),
+    ("memory_profiler_virtual_memory_bytes", "bytes", "Virtual memory size of the process.", |p| {
+        p.virtual_memory_kb.map(|kb| kb as f64 * 1024.0)
+    }),
+    ("memory_profiler_anonymous_memory_bytes", "bytes", "Anonymous resident memory of the process.", |p| {
+        p.anonymous_kb.map(|kb| kb as f64 * 1024.0)
+    }),
+    ("memory_profiler_file_backed_memory_bytes", "bytes", "File-backed resident memory of the process.", |p| {
+        p.file_backed_kb.map(|kb| kb as f64 * 1024.0)
+    }),
+    ("memory_profiler_swap_bytes", "bytes", "Swapped-out memory of the process.", |p| {
+        p.swap_kb.map(|kb| kb as f64 * 1024.0)
+    }),
+    ("memory_profiler_cpu_usage_percent", "percent", "CPU usage of the process (100 per core).", |p| {
+        p.cpu_percent.map(f64::from)
+    }),
+    ("memory_profiler_threads", "", "Number of threads of the process.", |p| {
+        p.threads.map(f64::from)
+    }),
+    ("memory_profiler_open_fds", "", "Number of open file descriptors of the process.", |p| {
+        p.open_fds.map(f64::from)
+    }),
+];
+
+/// 最新のサンプルとサンプリング時間の分布を、OpenMetricsのテキストにする
+pub fn render(points: &[MemDataPoint], latency: &LatencyHistogram) -> String {
+    let mut out = String::new();
+
+    for (name, unit, help, value) in PROCESS_GAUGES {
+        writeln!(out, "# TYPE {} gauge", name).unwrap();
+        if !unit.is_empty() {
+            writeln!(out, "# UNIT {} {}", name, unit).unwrap();
+        }
+        writeln!(out, "# HELP {} {}", name, help).unwrap();
+        for point in points {
+            if let Some(v) = value(point) {
+                writeln!(
+                    out,
+                    "{}{{pid=\"{}\",name=\"{}\"}} {}",
+                    name,
+                    point.pid,
+                    escape_label(&point.name),
+                    v
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    let name = "memory_profiler_sample_duration_seconds";
+    writeln!(out, "# TYPE {} histogram", name).unwrap();
+    writeln!(out, "# UNIT {} seconds", name).unwrap();
+    writeln!(out, "# HELP {} Time taken to sample all monitored processes.", name).unwrap();
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(&latency.counts) {
+        cumulative += count;
+        // `le` は `1.0` のように小数点付きで書く
+        writeln!(out, "{}_bucket{{le=\"{:?}\"}} {}", name, bound, cumulative).unwrap();
+    }
+    writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, latency.count).unwrap();
+    writeln!(out, "{}_sum {}", name, latency.sum).unwrap();
+    writeln!(out, "{}_count {}", name, latency.count).unwrap();
+
+    out.push_str("# EOF\n");
+    out
+}
+
+// ラベル値の `\`、`"`、改行をエスケープする
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_process_gauges() {
+        let point = MemDataPoint {
+            pid: 42,
+            name: "my \"app\"".to_string(),
+            memory_kb: 2,
+            threads: Some(3),
+            ..Default::default()
+        };
+        let text = render(&[point], &LatencyHistogram::default());
+
+        assert!(text.contains("memory_profiler_resident_memory_bytes{pid=\"42\",name=\"my \\\"app\\\"\"} 2048\n"));
+        assert!(text.contains("memory_profiler_threads{pid=\"42\",name=\"my \\\"app\\\"\"} 3\n"));
+        // 取れなかった指標はサンプルを出さない（メタデータだけ残る）
+        assert!(text.contains("# TYPE memory_profiler_swap_bytes gauge\n"));
+        assert!(!text.contains("memory_profiler_swap_bytes{"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_latency_buckets_are_cumulative() {
+        let mut latency = LatencyHistogram::default();
+        for seconds in [0.0005, 0.003, 0.004, 2.0] {
+            latency.observe(seconds);
+        }
+        let text = render(&[], &latency);
+
+        assert!(text.contains("memory_profiler_sample_duration_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(text.contains("memory_profiler_sample_duration_seconds_bucket{le=\"0.005\"} 3\n"));
+        assert!(text.contains("memory_profiler_sample_duration_seconds_bucket{le=\"1.0\"} 3\n"));
+        assert!(text.contains("memory_profiler_sample_duration_seconds_bucket{le=\"+Inf\"} 4\n"));
+        assert!(text.contains("memory_profiler_sample_duration_seconds_count 4\n"));
+    }
+}
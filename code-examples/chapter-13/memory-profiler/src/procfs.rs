@@ -0,0 +1,101 @@
+// sysinfoでは取れない情報を `/proc/<pid>/` から読む（Linux専用）
+//
+// 読めない場合（他のOS、権限不足、プロセスの終了など）は `None` を返し、
+// サンプルの該当フィールドを空のままにする。
+
+/// `/proc/<pid>/smaps_rollup` から求めたメモリの内訳（KB）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SmapsRollup {
+    pub anonymous_kb: u64,
+    pub file_backed_kb: u64,
+    pub swap_kb: u64,
+}
+
+#[cfg(target_os = "linux")]
+pub fn smaps_rollup(pid: u32) -> Option<SmapsRollup> {
+    let text = std::fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)).ok()?;
+    parse_smaps_rollup(&text)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn smaps_rollup(_pid: u32) -> Option<SmapsRollup> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+pub fn open_fds(pid: u32) -> Option<u32> {
+    let entries = std::fs::read_dir(format!("/proc/{}/fd", pid)).ok()?;
+    Some(entries.count() as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open_fds(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+pub fn thread_count(pid: u32) -> Option<u32> {
+    let text = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    text.lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn thread_count(_pid: u32) -> Option<u32> {
+    None
+}
+
+// `Rss:  1234 kB` のような行からKB単位の値を集める
+// ファイルに紐づくページは `Rss` から匿名ページを引いたもの（共有メモリを含む）とする
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_smaps_rollup(text: &str) -> Option<SmapsRollup> {
+    let field = |name: &str| -> Option<u64> {
+        text.lines().find_map(|line| {
+            let rest = line.strip_prefix(name)?.strip_prefix(':')?;
+            rest.trim().trim_end_matches("kB").trim().parse().ok()
+        })
+    };
+    let rss = field("Rss")?;
+    let anonymous_kb = field("Anonymous")?;
+    Some(SmapsRollup {
+        anonymous_kb,
+        file_backed_kb: rss.saturating_sub(anonymous_kb),
+        swap_kb: field("Swap").unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let text = "\
+55d0c6a3e000-7ffd3b5fb000 ---p 00000000 00:00 0                          [rollup]
+Rss:               12340 kB
+Pss:                9000 kB
+Pss_Anon:           4000 kB
+Anonymous:          4100 kB
+Swap:                256 kB
+SwapPss:             256 kB
+";
+        assert_eq!(
+            parse_smaps_rollup(text),
+            Some(SmapsRollup {
+                anonymous_kb: 4100,
+                file_backed_kb: 8240,
+                swap_kb: 256,
+            })
+        );
+        assert_eq!(parse_smaps_rollup("Pss: 1 kB\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reads_own_process() {
+        let pid = std::process::id();
+        assert!(thread_count(pid).unwrap() >= 1);
+        assert!(open_fds(pid).unwrap() >= 1);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+
+use crate::procfs;
+use crate::target::TargetSpec;
+
+/// 1回のサンプリングで得られた、1プロセス分のメモリ使用量とその他の指標
+///
+/// WebSocketへの送信と、履歴ファイル（CSV）の1行の両方に使う。
+/// RSS以外の指標はOSや権限によって取れないことがあるため `Option` にしている
+/// （JSONでは `null`、CSVでは空欄になる）。古い履歴ファイルにない列は既定値で読む。
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MemDataPoint {
+    pub timestamp: u64,
+    #[serde(default)]
+    pub pid: u32,
+    #[serde(default)]
+    pub name: String,
+    /// 常駐メモリ（RSS）。単位はKiB
+    /// （sysinfo 0.29 の `memory()` はバイトを返すので1024で割る）
+    pub memory_kb: u64,
+    #[serde(default)]
+    pub virtual_memory_kb: Option<u64>,
+    /// 1コアを100%とするCPU使用率
+    #[serde(default)]
+    pub cpu_percent: Option<f32>,
+    #[serde(default)]
+    pub threads: Option<u32>,
+    #[serde(default)]
+    pub open_fds: Option<u32>,
+    /// 前回のサンプルからのディスク読み込み量
+    #[serde(default)]
+    pub disk_read_bytes: Option<u64>,
+    /// 前回のサンプルからのディスク書き込み量
+    #[serde(default)]
+    pub disk_written_bytes: Option<u64>,
+    /// `smaps_rollup` による内訳（Linuxのみ）
+    #[serde(default)]
+    pub anonymous_kb: Option<u64>,
+    #[serde(default)]
+    pub file_backed_kb: Option<u64>,
+    #[serde(default)]
+    pub swap_kb: Option<u64>,
+}
+
+/// 現在のUNIX時刻（秒）
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// プロセス一覧を更新し、監視対象の全プロセスのサンプルを取る
+///
+/// 子プロセスの生成・終了に追従するため、毎回プロセス一覧全体を更新する。
+pub fn sample_targets(sys: &mut System, targets: &TargetSpec) -> Vec<MemDataPoint> {
+    sys.refresh_processes();
+    let timestamp = unix_now();
+
+    targets
+        .resolve(sys)
+        .into_iter()
+        .filter_map(|pid| {
+            let process = sys.process(pid)?;
+            let pid = pid.as_u32();
+            let disk = process.disk_usage();
+            let smaps = procfs::smaps_rollup(pid);
+            Some(MemDataPoint {
+                timestamp,
+                pid,
+                name: process.name().to_string(),
+                // sysinfoはバイト単位で返すので、KBに換算する
+                memory_kb: process.memory() / 1024,
+                virtual_memory_kb: Some(process.virtual_memory() / 1024),
+                cpu_percent: Some(process.cpu_usage()),
+                threads: procfs::thread_count(pid),
+                open_fds: procfs::open_fds(pid),
+                disk_read_bytes: Some(disk.read_bytes),
+                disk_written_bytes: Some(disk.written_bytes),
+                anonymous_kb: smaps.map(|s| s.anonymous_kb),
+                file_backed_kb: smaps.map(|s| s.file_backed_kb),
+                swap_kb: smaps.map(|s| s.swap_kb),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use sysinfo::{System, SystemExt};
+use tokio::sync::broadcast;
+use tokio::time::{interval, Duration, Instant};
+
+use crate::alert::{Alert, AlertEngine};
+use crate::metrics::LatencyHistogram;
+use crate::sample::{sample_targets, unix_now, MemDataPoint};
+use crate::target::TargetSpec;
+
+/// 1回のサンプリングで得られた、監視対象の全プロセス分のサンプル
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub timestamp: u64,
+    pub points: Vec<MemDataPoint>,
+    /// このサンプリングで新たに発火したアラート
+    pub alerts: Vec<Alert>,
+}
+
+/// 全クライアントで共有する唯一のサンプラー
+///
+/// バックグラウンドのタスクが一定間隔でプロセスを1回だけ走査し、結果を
+/// `broadcast` チャネルで配信する。新しく接続したクライアントのために、
+/// 直近のバッチをリングバッファに残しておく。
+pub struct Sampler {
+    targets: TargetSpec,
+    sender: broadcast::Sender<Arc<Batch>>,
+    // 直近のバッチ。配信と同じロックの中で更新し、購読開始時の取りこぼしや重複を防ぐ
+    recent: Mutex<VecDeque<Arc<Batch>>>,
+    capacity: usize,
+    finished: AtomicBool,
+    // 1回のサンプリングにかかった時間（`/metrics` で公開する）
+    latency: Mutex<LatencyHistogram>,
+}
+
+impl Sampler {
+    /// `capacity` は新規クライアントへ送り直す直近のバッチ数
+    pub fn new(targets: TargetSpec, capacity: usize) -> Arc<Self> {
+        let (sender, _) = broadcast::channel(capacity.max(16));
+        Arc::new(Sampler {
+            targets,
+            sender,
+            recent: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            finished: AtomicBool::new(false),
+            latency: Mutex::new(LatencyHistogram::default()),
+        })
+    }
+
+    /// 監視対象がいなくなり、これ以上バッチが配信されない
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    /// 直近のバッチと、それ以降のバッチを受け取るレシーバを返す
+    pub fn subscribe(&self) -> (Vec<Arc<Batch>>, broadcast::Receiver<Arc<Batch>>) {
+        let recent = self.recent.lock().unwrap();
+        (recent.iter().cloned().collect(), self.sender.subscribe())
+    }
+
+    /// 最後に配信したバッチ
+    pub fn latest(&self) -> Option<Arc<Batch>> {
+        self.recent.lock().unwrap().back().cloned()
+    }
+
+    pub fn latency(&self) -> LatencyHistogram {
+        self.latency.lock().unwrap().clone()
+    }
+
+    /// このバッチを最後に配信を終えるべきか
+    pub fn is_final(&self, batch: &Batch) -> bool {
+        batch.points.is_empty() && !self.targets.waits_for_processes()
+    }
+
+    pub(crate) fn publish(&self, batch: Batch) {
+        let batch = Arc::new(batch);
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        if self.capacity > 0 {
+            recent.push_back(batch.clone());
+        }
+        // 購読者がいなくてもエラーになるだけなので無視する
+        let _ = self.sender.send(batch);
+    }
+
+    /// `period` ごとにサンプリングして配信する。監視対象がいなくなったら終了する
+    ///
+    /// 各バッチは `alerts` のルールで判定し、発火したアラートも一緒に配信する。
+    pub async fn run(self: Arc<Self>, period: Duration, mut alerts: AlertEngine) {
+        let mut sys = System::new_all();
+        let mut interval = interval(period);
+
+        loop {
+            interval.tick().await;
+            let started = Instant::now();
+            let points = sample_targets(&mut sys, &self.targets);
+            self.latency
+                .lock()
+                .unwrap()
+                .observe(started.elapsed().as_secs_f64());
+            let fired = alerts.evaluate(&points);
+            for alert in &fired {
+                alerts.dispatch(alert);
+            }
+            let batch = Batch {
+                timestamp: unix_now(),
+                points,
+                alerts: fired,
+            };
+            let is_final = self.is_final(&batch);
+            if is_final {
+                self.finished.store(true, Ordering::Release);
+            }
+            self.publish(batch);
+            if is_final {
+                tracing::info!("Target processes not found. Stopping sampler.");
+                break;
+            }
+        }
+    }
+}
+
+/// クライアントごとの送信間隔で、共有サンプラーのバッチを間引く
+pub struct Throttle {
+    interval_secs: u64,
+    last_sent: Option<u64>,
+}
+
+impl Throttle {
+    pub fn new(interval_secs: u64) -> Self {
+        Throttle {
+            interval_secs: interval_secs.max(1),
+            last_sent: None,
+        }
+    }
+
+    /// このバッチを送るべきなら `true` を返し、送信時刻を記録する
+    pub fn admit(&mut self, batch: &Batch) -> bool {
+        match self.last_sent {
+            Some(last) if batch.timestamp < last.saturating_add(self.interval_secs) => false,
+            _ => {
+                self.last_sent = Some(batch.timestamp);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(timestamp: u64) -> Batch {
+        Batch {
+            timestamp,
+            points: vec![MemDataPoint {
+                timestamp,
+                memory_kb: timestamp * 10,
+                ..Default::default()
+            }],
+            alerts: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_then_live_without_gaps() {
+        let sampler = Sampler::new(TargetSpec::default(), 3);
+        for t in 1..=5 {
+            sampler.publish(batch(t));
+        }
+
+        let (backfill, mut receiver) = sampler.subscribe();
+        sampler.publish(batch(6));
+
+        let backfilled: Vec<u64> = backfill.iter().map(|b| b.timestamp).collect();
+        assert_eq!(backfilled, vec![3, 4, 5]);
+        assert_eq!(receiver.recv().await.unwrap().timestamp, 6);
+    }
+
+    #[test]
+    fn test_throttle_admits_by_interval() {
+        let mut throttle = Throttle::new(5);
+        let admitted: Vec<u64> = (100..=112).filter(|&t| throttle.admit(&batch(t))).collect();
+        assert_eq!(admitted, vec![100, 105, 110]);
+
+        // 極端な間隔でも溢れず、最初の1つだけを送る
+        let mut throttle = Throttle::new(u64::MAX);
+        let admitted: Vec<u64> = (100..=112).filter(|&t| throttle.admit(&batch(t))).collect();
+        assert_eq!(admitted, vec![100]);
+    }
+
+    #[test]
+    fn test_final_batch() {
+        let sampler = Sampler::new(TargetSpec::default(), 1);
+        assert!(sampler.is_final(&Batch {
+            timestamp: 1,
+            points: vec![],
+            alerts: vec![],
+        }));
+        assert!(!sampler.is_final(&batch(1)));
+    }
+}
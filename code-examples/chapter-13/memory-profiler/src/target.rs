@@ -0,0 +1,130 @@
+use std::collections::{BTreeSet, HashMap};
+use sysinfo::{Pid, ProcessExt, System, SystemExt};
+
+/// 監視対象のプロセスの指定
+///
+/// PIDの直接指定とプロセス名のパターンを組み合わせられる。`include_children` が
+/// 真なら、一致したプロセスの子孫も全て対象にする。子プロセスは生成・終了に
+/// 追従するよう、サンプリングのたびに `resolve` で探し直す。
+#[derive(Debug, Clone, Default)]
+pub struct TargetSpec {
+    pub pids: Vec<Pid>,
+    pub name_patterns: Vec<String>,
+    pub include_children: bool,
+}
+
+impl TargetSpec {
+    pub fn is_empty(&self) -> bool {
+        self.pids.is_empty() && self.name_patterns.is_empty()
+    }
+
+    /// 名前のパターンを含む場合、対象が一時的に見つからなくても監視を続ける
+    pub fn waits_for_processes(&self) -> bool {
+        !self.name_patterns.is_empty()
+    }
+
+    /// 現在のプロセス一覧から対象のPIDを求める（昇順）
+    pub fn resolve(&self, sys: &System) -> Vec<Pid> {
+        self.resolve_in(
+            sys.processes()
+                .iter()
+                .map(|(&pid, process)| (pid, process.parent(), process.name())),
+        )
+    }
+
+    // プロセス一覧（PID, 親PID, 名前）から対象を求める
+    fn resolve_in<'a>(&self, processes: impl Iterator<Item = (Pid, Option<Pid>, &'a str)>) -> Vec<Pid> {
+        let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+        let mut alive = BTreeSet::new();
+        let mut matched = BTreeSet::new();
+        for (pid, parent, name) in processes {
+            alive.insert(pid);
+            if let Some(parent) = parent {
+                children.entry(parent).or_default().push(pid);
+            }
+            if self.name_patterns.iter().any(|p| glob_match(p, name)) {
+                matched.insert(pid);
+            }
+        }
+        matched.extend(self.pids.iter().filter(|pid| alive.contains(pid)));
+
+        if self.include_children {
+            let mut stack: Vec<Pid> = matched.iter().copied().collect();
+            while let Some(pid) = stack.pop() {
+                for &child in children.get(&pid).into_iter().flatten() {
+                    if matched.insert(child) {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        matched.into_iter().collect()
+    }
+}
+
+// `*` を任意の文字列として扱うグロブマッチ（`*` を含まなければ完全一致）
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Vec<(Pid, Option<Pid>, &'static str)> {
+        vec![
+            (Pid::from(1), None, "init"),
+            (Pid::from(10), Some(Pid::from(1)), "server"),
+            (Pid::from(11), Some(Pid::from(10)), "worker"),
+            (Pid::from(12), Some(Pid::from(11)), "worker"),
+            (Pid::from(20), Some(Pid::from(1)), "postgres"),
+            (Pid::from(21), Some(Pid::from(20)), "postgres: writer"),
+        ]
+    }
+
+    #[test]
+    fn test_resolve_pids_and_tree() {
+        let mut spec = TargetSpec {
+            pids: vec![Pid::from(10), Pid::from(99)],
+            ..Default::default()
+        };
+        assert_eq!(spec.resolve_in(table().into_iter()), vec![Pid::from(10)]);
+
+        spec.include_children = true;
+        assert_eq!(
+            spec.resolve_in(table().into_iter()),
+            vec![Pid::from(10), Pid::from(11), Pid::from(12)]
+        );
+    }
+
+    #[test]
+    fn test_resolve_by_name_pattern() {
+        let spec = TargetSpec {
+            name_patterns: vec!["postgres*".to_string(), "work*r".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            spec.resolve_in(table().into_iter()),
+            vec![Pid::from(11), Pid::from(12), Pid::from(20), Pid::from(21)]
+        );
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("server", "server"));
+        assert!(!glob_match("server", "server2"));
+        assert!(!glob_match("a*a", "a"));
+    }
+}
@@ -3,14 +3,7 @@ window.onload = function () {
     const chart = new Chart(ctx, {
         type: 'line',
         data: {
-            datasets: [{
-                label: 'Memory Usage (KB)',
-                borderColor: 'rgb(75, 192, 192)',
-                backgroundColor: 'rgba(75, 192, 192, 0.2)',
-                tension: 0.1,
-                data: [],
-                fill: true,
-            }]
+            datasets: []
         },
         options: {
             scales: {
@@ -29,6 +22,8 @@ window.onload = function () {
                 },
                 y: {
                     beginAtZero: true,
+                    // プロセスごとの使用量を積み上げて、合計が分かるようにする
+                    stacked: true,
                     title: {
                         display: true,
                         text: 'Memory (KB)'
@@ -41,12 +36,50 @@ window.onload = function () {
         }
     });
 
+    // PIDごとのデータセット
+    const datasets = new Map();
+    const palette = [
+        [75, 192, 192], [255, 99, 132], [54, 162, 235],
+        [255, 159, 64], [153, 102, 255], [255, 205, 86]
+    ];
+
+    function datasetFor(dataPoint) {
+        let dataset = datasets.get(dataPoint.pid);
+        if (!dataset) {
+            const [r, g, b] = palette[datasets.size % palette.length];
+            dataset = {
+                label: `${dataPoint.name} (${dataPoint.pid})`,
+                borderColor: `rgb(${r}, ${g}, ${b})`,
+                backgroundColor: `rgba(${r}, ${g}, ${b}, 0.2)`,
+                tension: 0.1,
+                data: [],
+                fill: true,
+            };
+            datasets.set(dataPoint.pid, dataset);
+            chart.data.datasets.push(dataset);
+        }
+        return dataset;
+    }
+
+    // 新しいアラートを一覧の先頭に追加する
+    const alertList = document.getElementById('alerts');
+    function showAlert(alert) {
+        const item = document.createElement('li');
+        const time = new Date(alert.timestamp * 1000).toLocaleTimeString();
+        item.textContent = `${time} [${alert.rule}] ${alert.name} (${alert.pid}): ${alert.message}`;
+        alertList.prepend(item);
+    }
+
     const ws = new WebSocket(`ws://${window.location.host}/ws`);
 
     ws.onmessage = function (event) {
         try {
             const dataPoint = JSON.parse(event.data);
-            const chartData = chart.data.datasets[0].data;
+            if (dataPoint.alert) {
+                showAlert(dataPoint.alert);
+                return;
+            }
+            const chartData = datasetFor(dataPoint).data;
 
             chartData.push({
                 x: dataPoint.timestamp * 1000, // Chart.jsはミリ秒を期待
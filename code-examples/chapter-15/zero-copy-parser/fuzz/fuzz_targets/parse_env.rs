@@ -0,0 +1,29 @@
+#![no_main]
+
+// `cargo +nightly fuzz run parse_env` で実行する
+//
+// 任意の入力に対して `parse_env` がパニックせず、必ず入力を消費しながら
+// 進むこと（`many0` と空にマッチしうるパーサーの組み合わせで無限ループしないこと）を確認する。
+
+use libfuzzer_sys::fuzz_target;
+use zero_copy_parser::{env_pairs, parse_env};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    // 行単位のパーサーは失敗時に止まるだけなので、`parse_env` 自体はエラーを返さない
+    let (remaining, map) = parse_env(input).expect("parse_env must not fail");
+    assert!(input.ends_with(remaining), "remaining input must be a suffix of the input");
+
+    // 1ペアにつき最低1バイト（キーの1文字目）は消費するので、ペア数は入力長を超えない
+    let mut pairs = env_pairs(input);
+    let mut count = 0;
+    for (key, _) in pairs.by_ref() {
+        assert!(map.contains_key(key));
+        count += 1;
+        assert!(count <= input.len(), "env_pairs is not making progress");
+    }
+    assert_eq!(pairs.remaining(), remaining);
+});
@@ -0,0 +1,232 @@
+//! パーサーへの入力（ファイル、標準入力、メモリマップ）の読み込み
+//!
+//! `parse_env` は `&str` へのスライスを返すゼロコピーパーサーなので、入力全体が
+//! メモリ上に連続して存在する必要がある。巨大なファイルではコピーを避けるために
+//! `memmap2` でファイルをメモリマップし、UTF-8として検証した上でそのまま渡す。
+
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// 入力の読み込みに失敗した理由
+#[derive(Debug)]
+pub enum InputError {
+    Io {
+        source_name: String,
+        error: io::Error,
+    },
+    /// 入力が UTF-8 として不正。`offset` は最初の不正なバイトの位置
+    InvalidUtf8 { source_name: String, offset: usize },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io { source_name, error } => {
+                write!(f, "failed to read '{}': {}", source_name, error)
+            }
+            InputError::InvalidUtf8 { source_name, offset } => write!(
+                f,
+                "'{}' is not valid UTF-8: invalid byte sequence at byte offset {}",
+                source_name, offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Io { error, .. } => Some(error),
+            InputError::InvalidUtf8 { .. } => None,
+        }
+    }
+}
+
+enum Buffer {
+    Owned(String),
+    // UTF-8として検証済みのマップ
+    Mapped(Mmap),
+}
+
+/// UTF-8として検証済みの入力全体
+///
+/// `as_str` で得たスライスを `parse_env` に渡す。
+pub struct EnvInput {
+    name: String,
+    buffer: Buffer,
+}
+
+impl EnvInput {
+    /// ファイル全体をヒープに読み込む
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, InputError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let bytes = std::fs::read(path).map_err(|error| io_error(&name, error))?;
+        Self::from_bytes(name, bytes)
+    }
+
+    /// ファイルをメモリマップする（内容はコピーされない）
+    ///
+    /// マップしている間に他のプロセスがファイルを書き換えると、検証済みの内容が
+    /// 変わってしまう可能性がある。入力ファイルが変更されないことを前提とする。
+    pub fn map(path: impl AsRef<Path>) -> Result<Self, InputError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let file = File::open(path).map_err(|error| io_error(&name, error))?;
+        let len = file.metadata().map_err(|error| io_error(&name, error))?.len();
+        if len == 0 {
+            // 長さ0のマップは作れないプラットフォームがあるため、空文字列として扱う
+            return Ok(EnvInput {
+                name,
+                buffer: Buffer::Owned(String::new()),
+            });
+        }
+        // `Mmap::map` はファイルが外部から変更されると未定義動作になりうるため `unsafe`
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|error| io_error(&name, error))?;
+        validate_utf8(&name, &mmap)?;
+        Ok(EnvInput {
+            name,
+            buffer: Buffer::Mapped(mmap),
+        })
+    }
+
+    /// 標準入力を終端まで読み込む
+    pub fn stdin() -> Result<Self, InputError> {
+        let name = "<stdin>".to_string();
+        let mut bytes = Vec::new();
+        io::stdin()
+            .lock()
+            .read_to_end(&mut bytes)
+            .map_err(|error| io_error(&name, error))?;
+        Self::from_bytes(name, bytes)
+    }
+
+    /// 任意のバイト列を入力として扱う
+    pub fn from_bytes(name: impl Into<String>, bytes: Vec<u8>) -> Result<Self, InputError> {
+        let name = name.into();
+        match String::from_utf8(bytes) {
+            Ok(s) => Ok(EnvInput {
+                name,
+                buffer: Buffer::Owned(s),
+            }),
+            Err(e) => Err(InputError::InvalidUtf8 {
+                offset: e.utf8_error().valid_up_to(),
+                source_name: name,
+            }),
+        }
+    }
+
+    /// 入力元の名前（ファイルパスまたは `<stdin>`）
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.buffer, Buffer::Mapped(_))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.buffer {
+            Buffer::Owned(s) => s,
+            // SAFETY: `map` で UTF-8 として検証済みで、マップは読み取り専用
+            Buffer::Mapped(mmap) => unsafe { std::str::from_utf8_unchecked(mmap) },
+        }
+    }
+}
+
+/// コマンドラインで指定される入力元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    Stdin,
+    Path(PathBuf),
+}
+
+impl InputSource {
+    /// `-` を標準入力、それ以外をファイルパスとして解釈する
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::Path(PathBuf::from(arg))
+        }
+    }
+
+    /// 入力を読み込む。`mmap` が真ならファイルはメモリマップする
+    /// （標準入力はマップできないため常に読み込む）
+    pub fn open(&self, mmap: bool) -> Result<EnvInput, InputError> {
+        match self {
+            InputSource::Stdin => EnvInput::stdin(),
+            InputSource::Path(path) if mmap => EnvInput::map(path),
+            InputSource::Path(path) => EnvInput::read(path),
+        }
+    }
+}
+
+fn io_error(name: &str, error: io::Error) -> InputError {
+    InputError::Io {
+        source_name: name.to_string(),
+        error,
+    }
+}
+
+fn validate_utf8(name: &str, bytes: &[u8]) -> Result<(), InputError> {
+    std::str::from_utf8(bytes)
+        .map(|_| ())
+        .map_err(|e| InputError::InvalidUtf8 {
+            source_name: name.to_string(),
+            offset: e.valid_up_to(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_env;
+    use std::io::Write;
+
+    // テストごとに一意な一時ファイルを作る
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("zero-copy-parser-{}-{}", std::process::id(), name));
+        File::create(&path).unwrap().write_all(contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_map_and_read_agree() {
+        let path = temp_file("agree.env", b"PORT=8080\nHOST=localhost\n");
+        let mapped = EnvInput::map(&path).unwrap();
+        let read = EnvInput::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(mapped.is_mapped());
+        let (_, mapped_env) = parse_env(mapped.as_str()).unwrap();
+        let (_, read_env) = parse_env(read.as_str()).unwrap();
+        assert_eq!(mapped_env, read_env);
+        assert_eq!(mapped_env.get("PORT"), Some(&"8080"));
+    }
+
+    #[test]
+    fn test_invalid_utf8_reports_byte_offset() {
+        let path = temp_file("invalid.env", b"PORT=8080\nNAME=caf\xe9\n");
+        let err = InputSource::Path(path.clone()).open(true).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        match err {
+            InputError::InvalidUtf8 { offset, .. } => assert_eq!(offset, 18),
+            other => panic!("unexpected error: {}", other),
+        }
+        let err = EnvInput::from_bytes("bytes", b"\xff".to_vec()).err().unwrap();
+        assert!(err.to_string().contains("byte offset 0"));
+    }
+
+    #[test]
+    fn test_empty_file_can_be_mapped() {
+        let path = temp_file("empty.env", b"");
+        let input = EnvInput::map(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(input.as_str(), "");
+    }
+}
@@ -1,31 +1,112 @@
-use std::fs;
-use zero_copy_parser::parse_env;
+use std::io::IsTerminal;
+use zero_copy_parser::{env_pairs, InputSource, SecretPatterns};
 
-fn main() {
-    let file_path = "example.env";
-    println!("Parsing file: {}", file_path);
+const USAGE: &str = "\
+Usage: zero-copy-parser [OPTIONS] [PATH]...
+
+Parses each .env file and prints its key/value pairs in file order.
+Use `-` as PATH to read from stdin. Without PATH, stdin is read when it is
+piped and `example.env` otherwise.
+
+Options:
+  --mmap                      memory-map input files instead of reading them
+  --reveal                    print secret values instead of masking them
+  --secret-pattern <PATTERN>  treat keys matching PATTERN (e.g. `*_CERT`) as secrets";
+
+struct Options {
+    sources: Vec<InputSource>,
+    mmap: bool,
+    reveal: bool,
+    patterns: SecretPatterns,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        sources: Vec::new(),
+        mmap: false,
+        reveal: false,
+        patterns: SecretPatterns::default(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mmap" => options.mmap = true,
+            // 秘密情報はデフォルトでマスクし、`--reveal` が指定された場合のみ表示する
+            "--reveal" => options.reveal = true,
+            "--secret-pattern" => match args.next() {
+                Some(pattern) => options.patterns.add(pattern),
+                None => return Err("--secret-pattern requires a value".to_string()),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "-" => options.sources.push(InputSource::Stdin),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ => options.sources.push(InputSource::from_arg(&arg)),
+        }
+    }
+    if options.sources.is_empty() {
+        // パスが無ければ、標準入力がパイプされていればそれを、そうでなければ example.env を読む
+        if std::io::stdin().is_terminal() {
+            options.sources.push(InputSource::from_arg("example.env"));
+        } else {
+            options.sources.push(InputSource::Stdin);
+        }
+    }
+    Ok(options)
+}
+
+// パースできなかった位置を報告する
+// 残りの入力には秘密情報が含まれうるため、`--reveal` が無ければ行番号だけを表示する
+fn report_unparsed(input: &str, remaining: &str, reveal: bool) {
+    let offset = input.len() - remaining.len();
+    let line = input[..offset].matches('\n').count() + 1;
+    println!("\nWarning: input was not parsed from line {} onwards", line);
+    if reveal {
+        println!("{}", remaining);
+    }
+}
 
-    let content = match fs::read_to_string(file_path) {
-        Ok(c) => c,
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
         Err(e) => {
-            eprintln!("Failed to read file '{}': {}", file_path, e);
-            std::process::exit(1);
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
         }
     };
 
-    match parse_env(&content) {
-        Ok((remaining, map)) => {
-            println!("Successfully parsed .env file:");
-            for (key, value) in map {
-                println!("  {} = \"{}\"", key, value);
+    let mut failed = false;
+    for source in &options.sources {
+        let input = match source.open(options.mmap) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Failed to read input: {}", e);
+                failed = true;
+                continue;
             }
-            if !remaining.trim().is_empty() {
-                println!("\nWarning: some input was not parsed:");
-                println!("{}", remaining);
+        };
+        println!("Parsing file: {}", input.name());
+
+        // 中間のマップを作らず、入力を先頭から1組ずつパースしながら表示する
+        println!("Successfully parsed .env file:");
+        let mut pairs = env_pairs(input.as_str());
+        for (key, value) in pairs.by_ref() {
+            let value = options.patterns.classify_value(key, value);
+            if options.reveal {
+                println!("  {} = \"{}\"", key, value.expose());
+            } else {
+                println!("  {} = \"{}\"", key, value);
             }
         }
-        Err(e) => {
-            eprintln!("Failed to parse .env file: {:?}", e);
+        let remaining = pairs.remaining();
+        if !remaining.trim().is_empty() {
+            report_unparsed(input.as_str(), remaining, options.reveal);
         }
     }
-}
\ No newline at end of file
+
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,207 @@
+//! 秘密情報らしきキーの判定と、値のマスキング
+//!
+//! `API_KEY_SECRET=sk_67890` のような値をうっかりログや標準出力に出さないように、
+//! キー名のパターン（`*_SECRET`, `*_KEY`, `*PASSWORD*` など）で秘密情報を分類し、
+//! `Debug`/`Display` で値を表示しない `Secret` 型で包む。
+
+use serde::de::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+
+const REDACTED: &str = "********";
+
+/// `Debug`/`Display` で中身を表示しないラッパー型
+///
+/// 値が必要な場合は `expose` で明示的に取り出す。
+/// `from_str` で設定構造体のフィールドとして使うこともできる。
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// マスクされていない値を返す
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+/// 秘密情報とみなすキー名のパターン集合
+///
+/// パターンは `*` を任意の文字列として扱うグロブで、大文字・小文字は区別しない。
+#[derive(Debug, Clone)]
+pub struct SecretPatterns {
+    patterns: Vec<String>,
+}
+
+impl SecretPatterns {
+    /// 空のパターン集合を作る（何も秘密情報として扱わない）
+    pub fn empty() -> Self {
+        SecretPatterns { patterns: Vec::new() }
+    }
+
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        SecretPatterns {
+            patterns: patterns.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn add(&mut self, pattern: impl Into<String>) {
+        self.patterns.push(pattern.into());
+    }
+
+    pub fn is_secret(&self, key: &str) -> bool {
+        self.patterns.iter().any(|p| glob_match(p.as_bytes(), key.as_bytes()))
+    }
+
+    /// キーがパターンに一致すれば値を `Secret` で包む
+    /// `env_pairs` で1組ずつ処理する場合に使う
+    pub fn classify_value<'a>(&self, key: &str, value: &'a str) -> EnvValue<'a> {
+        if self.is_secret(key) {
+            EnvValue::Secret(Secret::new(value))
+        } else {
+            EnvValue::Plain(value)
+        }
+    }
+}
+
+impl Default for SecretPatterns {
+    fn default() -> Self {
+        SecretPatterns::new(["*_SECRET", "*_KEY", "*PASSWORD*", "*_TOKEN"])
+    }
+}
+
+// `*` のみをサポートする、大文字・小文字を区別しないグロブマッチ
+// 最後に見た `*` の位置に戻るバックトラッキングで、O(n*m) に収まる
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p].eq_ignore_ascii_case(&text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((sp, st)) = star {
+            // `*` にもう1文字多くマッチさせてやり直す
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// 秘密情報かどうかで分類された .env の値
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvValue<'a> {
+    Plain(&'a str),
+    Secret(Secret<&'a str>),
+}
+
+impl<'a> EnvValue<'a> {
+    pub fn is_secret(&self) -> bool {
+        matches!(self, EnvValue::Secret(_))
+    }
+
+    /// マスクされていない値を返す
+    pub fn expose(&self) -> &'a str {
+        match self {
+            EnvValue::Plain(v) => v,
+            EnvValue::Secret(s) => s.expose(),
+        }
+    }
+}
+
+impl fmt::Display for EnvValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvValue::Plain(v) => f.write_str(v),
+            EnvValue::Secret(s) => fmt::Display::fmt(s, f),
+        }
+    }
+}
+
+/// `parse_env` の結果を、パターンに一致するキーの値を `Secret` で包んだマップに変換する
+pub fn classify<'a>(
+    map: &HashMap<&'a str, &'a str>,
+    patterns: &SecretPatterns,
+) -> HashMap<&'a str, EnvValue<'a>> {
+    map.iter()
+        .map(|(&key, &value)| (key, patterns.classify_value(key, value)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_env;
+
+    #[test]
+    fn test_default_patterns() {
+        let patterns = SecretPatterns::default();
+        assert!(patterns.is_secret("API_KEY_SECRET"));
+        assert!(patterns.is_secret("API_KEY"));
+        assert!(patterns.is_secret("DB_PASSWORD_FILE"));
+        assert!(patterns.is_secret("github_token"));
+        assert!(!patterns.is_secret("API_KEY_PUBLIC"));
+        assert!(!patterns.is_secret("PORT"));
+        assert!(!SecretPatterns::empty().is_secret("API_KEY"));
+    }
+
+    #[test]
+    fn test_classify_redacts_debug_and_display() {
+        let (_, map) = parse_env("PORT=8080\nAPI_KEY_SECRET=sk_67890\n").unwrap();
+        let values = classify(&map, &SecretPatterns::default());
+
+        let secret = &values["API_KEY_SECRET"];
+        assert!(secret.is_secret());
+        assert_eq!(secret.to_string(), "********");
+        assert!(!format!("{:?}", values).contains("sk_67890"));
+        assert_eq!(secret.expose(), "sk_67890");
+        assert_eq!(values["PORT"].to_string(), "8080");
+    }
+
+    #[test]
+    fn test_secret_in_typed_config() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Config {
+            api_key: Secret<String>,
+        }
+
+        let config: Config = crate::from_str("API_KEY=sk_12345\n").unwrap();
+        assert_eq!(config.api_key.expose(), "sk_12345");
+        assert!(!format!("{:?}", config).contains("sk_12345"));
+    }
+}
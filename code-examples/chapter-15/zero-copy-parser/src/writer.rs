@@ -0,0 +1,316 @@
+//! .env 形式での書き出し
+//!
+//! マップ全体を .env テキストに書き出す `to_env_string` と、既存のファイルを
+//! 行単位で保持したまま一部のキーだけを書き換える `EnvDocument` を提供する。
+//! 値は必要な場合にだけダブルクォートで囲み、エスケープする。
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{parse_key, parse_pair};
+
+/// キー名として使えない文字列が渡された
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKey(pub String);
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid .env key {:?}: keys must start with a letter followed by letters, digits or '_'",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidKey {}
+
+fn validate_key(key: &str) -> Result<(), InvalidKey> {
+    match parse_key(key) {
+        Ok(("", _)) => Ok(()),
+        _ => Err(InvalidKey(key.to_string())),
+    }
+}
+
+// そのまま書くとパース結果が変わってしまう値かどうか
+// - 改行を含む（1行に収まらない）
+// - 先頭・末尾が空白（`=` の後の空白は読み飛ばされ、末尾の空白は見落とされやすい）
+// - クォートで始まる（クォートされた値として解釈される）
+fn needs_quotes(value: &str) -> bool {
+    value.contains(['\n', '\r'])
+        || value.starts_with([' ', '\t', '"', '\''])
+        || value.ends_with([' ', '\t'])
+}
+
+/// 値を .env に書ける形式にする。必要な場合だけダブルクォートで囲んでエスケープする
+pub fn quote_value(value: &str) -> Cow<'_, str> {
+    if !needs_quotes(value) {
+        return Cow::Borrowed(value);
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+/// `parse_env` が返した生の値からクォートを外す
+///
+/// ダブルクォートの中ではエスケープ（`\"`, `\\`, `\n`, `\r`, `\t`）を解釈し、
+/// シングルクォートの中はそのまま扱う。クォートされていない値は借用のまま返す。
+pub fn unquote(raw: &str) -> Cow<'_, str> {
+    if raw.len() >= 2 && raw.starts_with('\'') && raw.ends_with('\'') {
+        return Cow::Borrowed(&raw[1..raw.len() - 1]);
+    }
+    if !(raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"')) {
+        return Cow::Borrowed(raw);
+    }
+    let inner = &raw[1..raw.len() - 1];
+    if !inner.contains('\\') {
+        return Cow::Borrowed(inner);
+    }
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            // `\"` と `\\`、および未知のエスケープは次の文字をそのまま使う
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    Cow::Owned(value)
+}
+
+/// キーと値の組を .env テキストに書き出す
+///
+/// 出力が入力の反復順に左右されないよう、キーでソートして書き出す。
+pub fn to_env_string<I, K, V>(pairs: I) -> Result<String, InvalidKey>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut pairs: Vec<(K, V)> = pairs.into_iter().collect();
+    pairs.sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+
+    let mut out = String::new();
+    for (key, value) in &pairs {
+        let key = key.as_ref();
+        validate_key(key)?;
+        out.push_str(key);
+        out.push('=');
+        out.push_str(&quote_value(value.as_ref()));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// 文書中の1行。`ending` は行末の改行（最終行で改行が無ければ空）
+struct DocLine<'a> {
+    content: Cow<'a, str>,
+    ending: &'a str,
+    // キーと値のペアの行であれば、キーの開始・終了位置と値の開始位置
+    pair: Option<(usize, usize, usize)>,
+}
+
+impl DocLine<'_> {
+    fn key(&self) -> Option<&str> {
+        self.pair.map(|(key_start, key_end, _)| &self.content[key_start..key_end])
+    }
+
+    fn raw_value(&self) -> Option<&str> {
+        self.pair.map(|(_, _, value_start)| &self.content[value_start..])
+    }
+}
+
+/// 元のテキストを行単位で保持する、編集可能な .env 文書
+///
+/// コメント・空行・パースできない行、および編集していないペアは元のバイト列の
+/// まま書き出されるため、`EnvDocument::parse(s).to_string() == s` が成り立つ。
+/// 値を書き換えた行も、キーと `=` 周りの書式はそのまま残す。
+pub struct EnvDocument<'a> {
+    lines: Vec<DocLine<'a>>,
+    newline: &'static str,
+}
+
+impl<'a> EnvDocument<'a> {
+    pub fn parse(input: &'a str) -> Self {
+        let mut newline = "\n";
+        let lines = input
+            .split_inclusive('\n')
+            .map(|line| {
+                let content = line
+                    .strip_suffix("\r\n")
+                    .or_else(|| line.strip_suffix('\n'))
+                    .unwrap_or(line);
+                let ending = &line[content.len()..];
+                if ending == "\r\n" {
+                    newline = "\r\n";
+                }
+                // `parse_env` と同じく行頭のインデントは読み飛ばす
+                let key_start = content.len() - content.trim_start_matches([' ', '\t']).len();
+                let pair = match parse_pair(&content[key_start..]) {
+                    Ok(("", (key, value))) => {
+                        Some((key_start, key_start + key.len(), content.len() - value.len()))
+                    }
+                    _ => None,
+                };
+                DocLine {
+                    content: Cow::Borrowed(content),
+                    ending,
+                    pair,
+                }
+            })
+            .collect();
+        EnvDocument { lines, newline }
+    }
+
+    // 同じキーが複数あれば、`parse_env` と同様に最後のものが有効
+    fn position(&self, key: &str) -> Option<usize> {
+        self.lines.iter().rposition(|line| line.key() == Some(key))
+    }
+
+    /// キーに対応する値（クォートを外したもの）を返す
+    pub fn get(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.position(key)
+            .and_then(|i| self.lines[i].raw_value())
+            .map(unquote)
+    }
+
+    /// 有効なキーと値の組を、ファイル内で最後に現れた位置の順に返す
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Cow<'_, str>)> {
+        let mut last = HashMap::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if let Some(key) = line.key() {
+                last.insert(key, i);
+            }
+        }
+        self.lines.iter().enumerate().filter_map(move |(i, line)| {
+            let key = line.key()?;
+            // 後で上書きされるキーは返さない
+            if last.get(key) != Some(&i) {
+                return None;
+            }
+            Some((key, unquote(line.raw_value()?)))
+        })
+    }
+
+    /// キーの値を設定する。既存のキーならその行の値だけを書き換え、
+    /// 無ければ文書の末尾に追加する
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), InvalidKey> {
+        validate_key(key)?;
+        let value = quote_value(value);
+        match self.position(key) {
+            Some(i) => {
+                let line = &mut self.lines[i];
+                let (_, _, value_start) = line.pair.expect("position only returns pair lines");
+                let mut content = line.content[..value_start].to_string();
+                content.push_str(&value);
+                line.content = Cow::Owned(content);
+            }
+            None => {
+                // 最終行が改行で終わっていなければ、改行を補ってから追加する
+                if let Some(last) = self.lines.last_mut() {
+                    if last.ending.is_empty() {
+                        last.ending = self.newline;
+                    }
+                }
+                self.lines.push(DocLine {
+                    content: Cow::Owned(format!("{}={}", key, value)),
+                    ending: self.newline,
+                    pair: Some((0, key.len(), key.len() + 1)),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// キーを削除する（同じキーの行は全て取り除く）。削除した場合は `true`
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(|line| line.key() != Some(key));
+        self.lines.len() != before
+    }
+}
+
+impl fmt::Display for EnvDocument<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            f.write_str(&line.content)?;
+            f.write_str(line.ending)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_only_where_needed() {
+        assert_eq!(quote_value("postgres://localhost/db"), "postgres://localhost/db");
+        assert_eq!(quote_value("has # and = inside"), "has # and = inside");
+        assert_eq!(quote_value(" padded "), "\" padded \"");
+        assert_eq!(quote_value("line1\nline2"), "\"line1\\nline2\"");
+        assert_eq!(quote_value("\"quoted\""), "\"\\\"quoted\\\"\"");
+
+        for value in ["plain", " padded ", "a\\b\n\"c\"\r\t", "'single'", ""] {
+            assert_eq!(unquote(&quote_value(value)), value);
+        }
+        assert_eq!(unquote("'raw \\n'"), "raw \\n");
+    }
+
+    #[test]
+    fn test_to_env_string() {
+        let text = to_env_string([("PORT", "8080"), ("GREETING", "hello world ")]).unwrap();
+        assert_eq!(text, "GREETING=\"hello world \"\nPORT=8080\n");
+        assert!(to_env_string([("1BAD", "x")]).is_err());
+    }
+
+    #[test]
+    fn test_document_edits_are_minimal() {
+        let input = "# Server\r\nHOST = 127.0.0.1\r\nPORT=8080\r\n\r\n# Keys\r\nAPI_KEY=old\r\nnot a pair";
+        let mut doc = EnvDocument::parse(input);
+        assert_eq!(doc.to_string(), input);
+
+        doc.set("HOST", "0.0.0.0").unwrap();
+        doc.set("NEW_KEY", "two words ").unwrap();
+        assert!(doc.remove("API_KEY"));
+        assert_eq!(
+            doc.to_string(),
+            "# Server\r\nHOST = 0.0.0.0\r\nPORT=8080\r\n\r\n# Keys\r\nnot a pair\r\nNEW_KEY=\"two words \"\r\n"
+        );
+        assert_eq!(doc.get("NEW_KEY").as_deref(), Some("two words "));
+        assert_eq!(
+            doc.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+            vec!["HOST", "PORT", "NEW_KEY"]
+        );
+    }
+
+    #[test]
+    fn test_document_keeps_indentation() {
+        let mut doc = EnvDocument::parse("  PORT=8080\n\tHOST=localhost\n");
+        assert_eq!(doc.get("PORT").as_deref(), Some("8080"));
+
+        doc.set("HOST", "0.0.0.0").unwrap();
+        assert_eq!(doc.to_string(), "  PORT=8080\n\tHOST=0.0.0.0\n");
+    }
+}
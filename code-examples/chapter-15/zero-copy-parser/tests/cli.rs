@@ -0,0 +1,54 @@
+// zero-copy-parser コマンドの統合テスト
+//
+// ビルドされたバイナリに標準入力から .env を渡し、出力を確認する。
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zero-copy-parser"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const MALFORMED: &str = "PORT=8080\n!!! broken line\nAPI_KEY=sk_live_12345\n";
+
+#[test]
+fn unparsed_input_is_not_printed_by_default() {
+    let output = run(&["-"], MALFORMED);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("PORT = \"8080\""));
+    assert!(stdout.contains("not parsed from line 2"));
+    assert!(!stdout.contains("sk_live_12345"), "stdout leaked a secret:\n{}", stdout);
+    assert!(!stdout.contains("broken line"));
+}
+
+#[test]
+fn unparsed_input_is_printed_with_reveal() {
+    let output = run(&["--reveal", "-"], MALFORMED);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("not parsed from line 2"));
+    assert!(stdout.contains("!!! broken line\nAPI_KEY=sk_live_12345"));
+}
+
+#[test]
+fn pairs_are_printed_in_file_order() {
+    let output = run(&[], "ZETA=1\nALPHA=2\nDB_PASSWORD=hunter2\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    let zeta = stdout.find("ZETA = \"1\"").unwrap();
+    let alpha = stdout.find("ALPHA = \"2\"").unwrap();
+    assert!(zeta < alpha);
+    assert!(stdout.contains("DB_PASSWORD = \"********\""));
+}
@@ -0,0 +1,161 @@
+// zero-copy-parser のプロパティベーステスト
+//
+// 有効な .env 文書を生成するジェネレータと、
+// 「マップを書き出してパースし直すと同じマップに戻る」というラウンドトリップ性を検証する。
+
+use proptest::prelude::*;
+use std::collections::HashMap;
+use zero_copy_parser::{env_pairs, parse_env, to_env_string, unquote, EnvDocument};
+
+// 有効なキー: 英字で始まり、英数字またはアンダースコアが続く
+fn key() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z0-9_]{0,15}"
+}
+
+// 有効な値: 改行を含まず、先頭は空白でない（`=` の後の空白は区切りとして読み飛ばされる）
+fn value() -> impl Strategy<Value = String> {
+    prop_oneof![Just(String::new()), "[^ \t\r\n][^\r\n]{0,30}"]
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    Pair {
+        key: String,
+        value: String,
+        before_eq: &'static str,
+        after_eq: &'static str,
+    },
+    Comment(String),
+    Blank(&'static str),
+}
+
+fn spaces() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just(""), Just(" "), Just("\t"), Just("  ")]
+}
+
+fn line() -> impl Strategy<Value = Line> {
+    prop_oneof![
+        4 => (key(), value(), spaces(), spaces()).prop_map(|(key, value, before_eq, after_eq)| {
+            Line::Pair { key, value, before_eq, after_eq }
+        }),
+        1 => "[^\r\n]{0,20}".prop_map(Line::Comment),
+        1 => spaces().prop_map(Line::Blank),
+    ]
+}
+
+// コメント・空行・`=` 前後の空白・CRLF を混ぜた有効な .env 文書と、
+// それをパースしたときに期待されるマップ（同じキーは後勝ち）を生成する
+fn document() -> impl Strategy<Value = (String, HashMap<String, String>)> {
+    (
+        prop::collection::vec(line(), 0..20),
+        prop_oneof![Just("\n"), Just("\r\n")],
+        any::<bool>(),
+    )
+        .prop_map(|(lines, newline, trailing_newline)| {
+            let mut text = String::new();
+            let mut expected = HashMap::new();
+            for (i, line) in lines.iter().enumerate() {
+                if i > 0 {
+                    text.push_str(newline);
+                }
+                match line {
+                    Line::Pair { key, value, before_eq, after_eq } => {
+                        text.push_str(&format!("{}{}={}{}", key, before_eq, after_eq, value));
+                        expected.insert(key.clone(), value.clone());
+                    }
+                    Line::Comment(comment) => text.push_str(&format!("#{}", comment)),
+                    Line::Blank(spaces) => text.push_str(spaces),
+                }
+            }
+            if trailing_newline && !lines.is_empty() {
+                text.push_str(newline);
+            }
+            (text, expected)
+        })
+}
+
+fn to_owned_map(map: HashMap<&str, &str>) -> HashMap<String, String> {
+    map.into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn roundtrip_map(map in prop::collection::hash_map(key(), value(), 0..20)) {
+        let text: String = map.iter().map(|(k, v)| format!("{}={}\n", k, v)).collect();
+        let (remaining, parsed) = parse_env(&text).unwrap();
+
+        prop_assert_eq!(remaining, "");
+        prop_assert_eq!(to_owned_map(parsed), map);
+    }
+
+    // `to_env_string` で書き出した値は、`parse_env` の生の値から `unquote` で元に戻る
+    #[test]
+    fn roundtrip_written_map(map in prop::collection::hash_map(key(), value(), 0..20)) {
+        let text = to_env_string(&map).unwrap();
+        let (remaining, parsed) = parse_env(&text).unwrap();
+        let parsed: HashMap<String, String> =
+            parsed.into_iter().map(|(k, v)| (k.to_string(), unquote(v).into_owned())).collect();
+
+        prop_assert_eq!(remaining, "");
+        prop_assert_eq!(parsed, map);
+    }
+
+    // 改行・前後の空白・クォートを含む任意の値も、書き出してから読み戻せる
+    #[test]
+    fn roundtrip_arbitrary_values(map in prop::collection::hash_map(key(), "(.|\r|\n)*", 0..20)) {
+        let text = to_env_string(&map).unwrap();
+        let doc = EnvDocument::parse(&text);
+        let parsed: HashMap<String, String> =
+            doc.iter().map(|(k, v)| (k.to_string(), v.into_owned())).collect();
+
+        prop_assert_eq!(parsed, map);
+    }
+
+    // 編集しなければ元の文書がバイト単位でそのまま書き出され、
+    // 1つのキーを書き換えても他の行は変わらない
+    #[test]
+    fn document_is_lossless((text, expected) in document(), new_value in "(.|\r|\n)*") {
+        let mut doc = EnvDocument::parse(&text);
+        prop_assert_eq!(doc.to_string(), text.clone());
+
+        if let Some(key) = expected.keys().next() {
+            doc.set(key, &new_value).unwrap();
+            let edited = doc.to_string();
+            let changed = text
+                .split('\n')
+                .zip(edited.split('\n'))
+                .filter(|(before, after)| before != after)
+                .count();
+            prop_assert!(changed <= 1);
+            let reparsed = EnvDocument::parse(&edited);
+            let value = reparsed.get(key);
+            prop_assert_eq!(value.as_deref(), Some(new_value.as_str()));
+        }
+    }
+
+    #[test]
+    fn parses_generated_documents((text, expected) in document()) {
+        let (remaining, parsed) = parse_env(&text).unwrap();
+
+        prop_assert_eq!(remaining, "");
+        prop_assert_eq!(to_owned_map(parsed), expected);
+    }
+
+    // fuzz/fuzz_targets/parse_env.rs と同じ不変条件を、任意の文字列で確認する
+    #[test]
+    fn never_panics_and_makes_progress(input in "(\\PC|[ \t\r\n=#])*") {
+        let (remaining, map) = parse_env(&input).unwrap();
+        prop_assert!(input.ends_with(remaining));
+
+        let mut pairs = env_pairs(&input);
+        let mut count = 0;
+        for (key, _) in pairs.by_ref() {
+            prop_assert!(map.contains_key(key));
+            count += 1;
+            prop_assert!(count <= input.len());
+        }
+        prop_assert_eq!(pairs.remaining(), remaining);
+    }
+}
@@ -0,0 +1,162 @@
+// コミット間の比較と回帰の判定
+//
+// 平均の変化が `noise` を超え、かつWelchのt検定で有意（p < `alpha`）なときだけ
+// 回帰（または改善）とみなす。どちらかだけでは、測定のゆらぎと区別できない。
+
+use crate::history::{Record, Run};
+use crate::stats;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// 有意水準
+    pub alpha: f64,
+    /// これより小さい変化（割合）は無視する
+    pub noise: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds { alpha: 0.05, noise: 0.05 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+}
+
+impl Verdict {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Regressed => "regressed",
+            Verdict::Improved => "improved",
+            Verdict::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// 2つの結果の比較
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Change {
+    /// 平均の変化の割合。+0.1 なら10%遅くなった
+    pub ratio: f64,
+    /// サンプルが足りず検定できなければ `None`
+    pub p: Option<f64>,
+    pub verdict: Verdict,
+}
+
+pub fn compare(before: &Record, after: &Record, thresholds: Thresholds) -> Change {
+    let ratio = after.mean_ns / before.mean_ns - 1.0;
+    let p = stats::welch_t_test(&before.samples_ns, &after.samples_ns).map(|test| test.p);
+    // 検定できないときは信頼区間が重ならないことで代える
+    let significant = match p {
+        Some(p) => p < thresholds.alpha,
+        None => after.ci_low_ns > before.ci_high_ns || after.ci_high_ns < before.ci_low_ns,
+    };
+    let verdict = if !significant || ratio.abs() <= thresholds.noise {
+        Verdict::Unchanged
+    } else if ratio > 0.0 {
+        Verdict::Regressed
+    } else {
+        Verdict::Improved
+    };
+    Change { ratio, p, verdict }
+}
+
+/// 1つのベンチマークについての、基準のコミットとの比較
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub benchmark: String,
+    /// 基準のコミット。以前の結果がなければ `None`
+    pub baseline: Option<String>,
+    pub before_ns: Option<f64>,
+    pub after_ns: f64,
+    pub change: Option<Change>,
+}
+
+/// `runs[target]` の各ベンチマークを比べる。`baseline` がなければ、そのベンチマークを
+/// 含む直前のコミットと比べる
+pub fn compare_runs(runs: &[Run], target: usize, baseline: Option<usize>, thresholds: Thresholds) -> Vec<Comparison> {
+    let run = &runs[target];
+    run.benchmarks
+        .iter()
+        .map(|(benchmark, after)| {
+            let previous = match baseline {
+                Some(index) => runs.get(index).filter(|base| base.benchmarks.contains_key(benchmark)),
+                None => runs[..target].iter().rev().find(|base| base.benchmarks.contains_key(benchmark)),
+            };
+            let before = previous.map(|base| (base, &base.benchmarks[benchmark]));
+            Comparison {
+                benchmark: benchmark.clone(),
+                baseline: before.map(|(base, _)| base.commit.clone()),
+                before_ns: before.map(|(_, record)| record.mean_ns),
+                after_ns: after.mean_ns,
+                change: before.map(|(_, record)| compare(record, after, thresholds)),
+            }
+        })
+        .collect()
+}
+
+pub fn count(comparisons: &[Comparison], verdict: Verdict) -> usize {
+    comparisons
+        .iter()
+        .filter(|c| c.change.is_some_and(|change| change.verdict == verdict))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::record;
+    use std::collections::BTreeMap;
+
+    const STEADY: [f64; 8] = [100.0, 101.0, 99.0, 100.5, 99.5, 100.0, 101.0, 99.0];
+
+    fn scaled(factor: f64) -> Vec<f64> {
+        STEADY.iter().map(|x| x * factor).collect()
+    }
+
+    #[test]
+    fn test_compare_needs_significance_and_size() {
+        let thresholds = Thresholds::default();
+        let base = record(&STEADY);
+        assert_eq!(compare(&base, &record(&scaled(1.2)), thresholds).verdict, Verdict::Regressed);
+        assert_eq!(compare(&base, &record(&scaled(0.8)), thresholds).verdict, Verdict::Improved);
+        // 有意でも小さすぎる変化
+        assert_eq!(compare(&base, &record(&scaled(1.03)), thresholds).verdict, Verdict::Unchanged);
+        // 大きくても、ばらつきに埋もれている変化
+        let noisy = record(&[50.0, 150.0, 60.0, 140.0]);
+        let shifted = record(&[60.0, 170.0, 70.0, 150.0]);
+        let change = compare(&noisy, &shifted, thresholds);
+        assert!(change.ratio > 0.05 && change.p.unwrap() > 0.05);
+        assert_eq!(change.verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn test_compare_runs_finds_previous_result() {
+        let run = |commit: &str, at: i64, benchmarks: Vec<(&str, Vec<f64>)>| Run {
+            commit: commit.to_string(),
+            committed_at: at,
+            benchmarks: benchmarks
+                .into_iter()
+                .map(|(id, samples)| (id.to_string(), record(&samples)))
+                .collect::<BTreeMap<_, _>>(),
+        };
+        let runs = [
+            run("aaaa", 1, vec![("x/sum", scaled(1.0)), ("y/fib", scaled(1.0))]),
+            run("bbbb", 2, vec![("x/sum", scaled(1.0))]),
+            run("cccc", 3, vec![("x/sum", scaled(1.5)), ("y/fib", scaled(1.0)), ("z/new", scaled(1.0))]),
+        ];
+        let comparisons = compare_runs(&runs, 2, None, Thresholds::default());
+        let baselines: Vec<_> = comparisons.iter().map(|c| c.baseline.as_deref()).collect();
+        assert_eq!(baselines, [Some("bbbb"), Some("aaaa"), None]);
+        assert_eq!(count(&comparisons, Verdict::Regressed), 1);
+        assert_eq!(count(&comparisons, Verdict::Unchanged), 1);
+
+        // 基準を指定すれば、そこにないベンチマークは比べない
+        let comparisons = compare_runs(&runs, 2, Some(1), Thresholds::default());
+        assert_eq!(comparisons.iter().filter(|c| c.baseline.is_some()).count(), 1);
+    }
+}
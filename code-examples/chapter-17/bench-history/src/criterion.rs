@@ -0,0 +1,291 @@
+// criterionの出力ディレクトリ（`target/criterion`）を読む
+//
+// 各ベンチマークの最新の結果は `<id>/new/` に、`benchmark.json`（ID）、
+// `estimates.json`（平均などの推定値）、`sample.json`（生のサンプル）として置かれる。
+// `base/` や `--save-baseline` で保存したものは読まない。
+//
+// criterionは今回実行しなかったベンチマーク（`cargo bench -- <filter>` で除外したもの、
+// 削除・改名したもの）の `new/` を消さない。古い結果を今のコミットとして記録しないよう、
+// `estimates.json` の更新時刻が最新の実行から離れているものは読み飛ばす。
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::history::Record;
+
+/// criterionの出力を読めなかった理由
+#[derive(Debug)]
+pub enum CriterionError {
+    Io { path: String, error: std::io::Error },
+    Parse { path: String, message: String },
+    /// ベンチマークの結果が1つもない
+    Empty { path: String },
+}
+
+impl fmt::Display for CriterionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CriterionError::Io { path, error } => write!(f, "{}: {}", path, error),
+            CriterionError::Parse { path, message } => write!(f, "{}: {}", path, message),
+            CriterionError::Empty { path } => {
+                write!(f, "{}: no criterion results found (run `cargo bench` first)", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CriterionError {}
+
+#[derive(Deserialize)]
+struct BenchmarkId {
+    full_id: String,
+}
+
+#[derive(Deserialize)]
+struct ConfidenceInterval {
+    lower_bound: f64,
+    upper_bound: f64,
+}
+
+#[derive(Deserialize)]
+struct Estimate {
+    confidence_interval: ConfidenceInterval,
+    point_estimate: f64,
+}
+
+#[derive(Deserialize)]
+struct Estimates {
+    mean: Estimate,
+    median: Estimate,
+    std_dev: Estimate,
+}
+
+#[derive(Deserialize)]
+struct Sample {
+    iters: Vec<f64>,
+    times: Vec<f64>,
+}
+
+/// クレートのディレクトリや `target/` が渡されても、その下の `target/criterion` を読む
+pub fn output_dir(path: &Path) -> PathBuf {
+    for candidate in [path.join("target").join("criterion"), path.join("criterion")] {
+        if candidate.is_dir() {
+            return candidate;
+        }
+    }
+    path.to_path_buf()
+}
+
+// Cargo.toml の `[package]` の `name`（TOMLの依存を増やさないよう、行単位で読む）
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if let Some(value) = line.strip_prefix("name").map(str::trim_start).and_then(|rest| rest.strip_prefix('=')) {
+            if in_package {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+/// 履歴に記録するプロジェクト名。`<crate>/target/criterion` ならクレートのパッケージ名
+/// （hands-on のクレートはどれも `rust/` にあるので、ディレクトリ名では区別できない）
+pub fn project_name(output_dir: &Path) -> String {
+    let absolute = output_dir.canonicalize().unwrap_or_else(|_| output_dir.to_path_buf());
+    let crate_dir = if absolute.ends_with("target/criterion") {
+        absolute.parent().and_then(Path::parent).unwrap_or(&absolute)
+    } else {
+        &absolute
+    };
+    std::fs::read_to_string(crate_dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|manifest| package_name(&manifest))
+        .or_else(|| crate_dir.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "criterion".to_string())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, CriterionError> {
+    let display = path.display().to_string();
+    let bytes = std::fs::read(path).map_err(|error| CriterionError::Io { path: display.clone(), error })?;
+    serde_json::from_slice(&bytes).map_err(|e| CriterionError::Parse {
+        path: display,
+        message: e.to_string(),
+    })
+}
+
+fn read_benchmark(dir: &Path) -> Result<(String, Record), CriterionError> {
+    let id: BenchmarkId = read_json(&dir.join("benchmark.json"))?;
+    let estimates: Estimates = read_json(&dir.join("estimates.json"))?;
+    let sample: Sample = read_json(&dir.join("sample.json"))?;
+    // 1サンプルは `iters` 回分の合計時間なので、1回あたりに直す
+    let samples_ns = sample
+        .iters
+        .iter()
+        .zip(&sample.times)
+        .filter(|(&iters, _)| iters > 0.0)
+        .map(|(iters, time)| time / iters)
+        .collect();
+    let record = Record {
+        mean_ns: estimates.mean.point_estimate,
+        ci_low_ns: estimates.mean.confidence_interval.lower_bound,
+        ci_high_ns: estimates.mean.confidence_interval.upper_bound,
+        median_ns: estimates.median.point_estimate,
+        stddev_ns: estimates.std_dev.point_estimate,
+        samples_ns,
+    };
+    Ok((id.full_id, record))
+}
+
+/// 1回の `cargo bench` で書かれたとみなす結果の間隔
+/// 更新時刻を新しい順に並べ、間がこれより空いたところから前は以前の実行の結果とする
+pub const RUN_GAP: Duration = Duration::from_secs(10 * 60);
+
+/// `read` の結果
+pub struct Latest {
+    /// 最新の実行の結果。キーはcriterionのID（`group/function/value`）
+    pub benchmarks: BTreeMap<String, Record>,
+    /// 以前の実行から残っていて読み飛ばしたベンチマークのID
+    pub stale: Vec<String>,
+}
+
+fn modified(path: &Path) -> Result<SystemTime, CriterionError> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|error| CriterionError::Io {
+            path: path.display().to_string(),
+            error,
+        })
+}
+
+fn walk(dir: &Path, benchmarks: &mut Vec<(SystemTime, String, Record)>) -> Result<(), CriterionError> {
+    let entries = std::fs::read_dir(dir).map_err(|error| CriterionError::Io {
+        path: dir.display().to_string(),
+        error,
+    })?;
+    for entry in entries {
+        let path = entry
+            .map_err(|error| CriterionError::Io {
+                path: dir.display().to_string(),
+                error,
+            })?
+            .path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.file_name().is_some_and(|name| name == "new") {
+            if path.join("benchmark.json").is_file() {
+                let (id, record) = read_benchmark(&path)?;
+                benchmarks.push((modified(&path.join("estimates.json"))?, id, record));
+            }
+        } else {
+            walk(&path, benchmarks)?;
+        }
+    }
+    Ok(())
+}
+
+/// `dir` 以下の最新の実行の結果を読む
+pub fn read(dir: &Path) -> Result<Latest, CriterionError> {
+    let mut found = Vec::new();
+    walk(dir, &mut found)?;
+    if found.is_empty() {
+        return Err(CriterionError::Empty {
+            path: dir.display().to_string(),
+        });
+    }
+    // 新しい順に並べ、間隔が `RUN_GAP` を超えるまでを最新の実行とする
+    found.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+    let run_len = found
+        .windows(2)
+        .position(|pair| pair[0].0.duration_since(pair[1].0).unwrap_or_default() > RUN_GAP)
+        .map_or(found.len(), |i| i + 1);
+    let stale_results = found.split_off(run_len);
+
+    let mut stale: Vec<String> = stale_results.into_iter().map(|(_, id, _)| id).collect();
+    stale.sort();
+    Ok(Latest {
+        benchmarks: found.into_iter().map(|(_, id, record)| (id, record)).collect(),
+        stale,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ESTIMATES: &str = r#"{"mean":{"confidence_interval":{"confidence_level":0.95,"lower_bound":13.5,"upper_bound":14.6},"point_estimate":14.0,"standard_error":0.29},
+        "median":{"confidence_interval":{"confidence_level":0.95,"lower_bound":12.8,"upper_bound":15.6},"point_estimate":13.7,"standard_error":0.74},
+        "median_abs_dev":{"confidence_interval":{"confidence_level":0.95,"lower_bound":2.8,"upper_bound":4.5},"point_estimate":3.8,"standard_error":0.44},
+        "slope":null,
+        "std_dev":{"confidence_interval":{"confidence_level":0.95,"lower_bound":2.6,"upper_bound":3.1},"point_estimate":2.9,"standard_error":0.11}}"#;
+
+    fn write_benchmark(root: &Path, id: &str, kind: &str) {
+        let dir = root.join(id).join(kind);
+        std::fs::create_dir_all(&dir).unwrap();
+        let benchmark = format!(r#"{{"group_id":"fibonacci","function_id":null,"value_str":null,"throughput":null,"full_id":"{}","directory_name":"{}","title":"{}"}}"#, id, id, id);
+        std::fs::write(dir.join("benchmark.json"), benchmark).unwrap();
+        std::fs::write(dir.join("estimates.json"), ESTIMATES).unwrap();
+        std::fs::write(dir.join("sample.json"), r#"{"sampling_mode":"Linear","iters":[10.0,20.0],"times":[150.0,260.0]}"#).unwrap();
+    }
+
+    #[test]
+    fn test_read_latest_results() {
+        let crate_dir = std::env::temp_dir().join(format!("bench-criterion-{}", std::process::id())).join("hands-on");
+        let root = crate_dir.join("target").join("criterion");
+        let _ = std::fs::remove_dir_all(&crate_dir);
+        write_benchmark(&root, "fibonacci/recursive/10", "new");
+        write_benchmark(&root, "fibonacci/recursive/10", "base");
+        write_benchmark(&root, "sum", "new");
+        std::fs::create_dir_all(root.join("report")).unwrap();
+
+        assert_eq!(output_dir(&crate_dir), root);
+        assert_eq!(project_name(&root), "hands-on");
+        std::fs::write(crate_dir.join("Cargo.toml"), "[package]\nname = \"fib-bench\"\n\n[lib]\nname = \"fib\"\n").unwrap();
+        assert_eq!(project_name(&root), "fib-bench");
+        let latest = read(&root).unwrap();
+        assert!(latest.stale.is_empty());
+        let benchmarks = latest.benchmarks;
+        assert_eq!(benchmarks.keys().collect::<Vec<_>>(), ["fibonacci/recursive/10", "sum"]);
+        let record = &benchmarks["sum"];
+        assert_eq!((record.mean_ns, record.ci_low_ns, record.ci_high_ns), (14.0, 13.5, 14.6));
+        assert_eq!(record.samples_ns, [15.0, 13.0]);
+
+        assert!(matches!(read(&root.join("report")), Err(CriterionError::Empty { .. })));
+        std::fs::remove_dir_all(crate_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_skips_results_left_from_earlier_runs() {
+        let root = std::env::temp_dir().join(format!("bench-criterion-stale-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        write_benchmark(&root, "renamed", "new");
+        write_benchmark(&root, "filtered/out", "new");
+        write_benchmark(&root, "current/a", "new");
+        write_benchmark(&root, "current/b", "new");
+
+        // 今回の実行の中の間隔は許し、それより前の実行の結果は読まない
+        let now = SystemTime::now();
+        let set_modified = |id: &str, at: SystemTime| {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(root.join(id).join("new").join("estimates.json"))
+                .unwrap();
+            file.set_modified(at).unwrap();
+        };
+        set_modified("current/a", now);
+        set_modified("current/b", now - RUN_GAP / 2);
+        set_modified("filtered/out", now - RUN_GAP * 3);
+        set_modified("renamed", now - RUN_GAP * 100);
+
+        let latest = read(&root).unwrap();
+        assert_eq!(latest.benchmarks.keys().collect::<Vec<_>>(), ["current/a", "current/b"]);
+        assert_eq!(latest.stale, ["filtered/out", "renamed"]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+// 結果を記録するコミットをgitに問い合わせる
+
+use std::path::Path;
+use std::process::Command;
+
+pub struct Commit {
+    pub id: String,
+    /// コミット日時（UNIX時間）
+    pub committed_at: i64,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `rev`（`HEAD` やブランチ名など）をコミットIDにする
+pub fn resolve(dir: &Path, rev: &str) -> Result<Commit, String> {
+    let id = git(dir, &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])?;
+    let committed_at = git(dir, &["show", "-s", "--format=%ct", &id])?;
+    let committed_at = committed_at
+        .parse()
+        .map_err(|_| format!("unexpected commit time `{}` for {}", committed_at, id))?;
+    Ok(Commit { id, committed_at })
+}
+
+/// 追跡しているファイルにコミットしていない変更があるか
+pub fn is_dirty(dir: &Path) -> bool {
+    git(dir, &["status", "--porcelain", "--untracked-files=no"]).is_ok_and(|status| !status.is_empty())
+}
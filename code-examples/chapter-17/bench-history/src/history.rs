@@ -0,0 +1,154 @@
+// ベンチマーク結果の履歴
+//
+// 1コミットの結果を `<commit>.json` として1ファイルに保存する。複数のクレートの
+// 結果を同じコミットに取り込めるよう、ベンチマークのIDは `<project>/<criterionのID>`。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 1つのベンチマークの結果（単位はすべて1回あたりのナノ秒）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub mean_ns: f64,
+    /// 平均の95%信頼区間（criterionのブートストラップによる）
+    pub ci_low_ns: f64,
+    pub ci_high_ns: f64,
+    pub median_ns: f64,
+    pub stddev_ns: f64,
+    /// 各サンプルの1回あたりの時間。有意差の検定に使う
+    pub samples_ns: Vec<f64>,
+}
+
+/// 1コミット分の結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    pub commit: String,
+    /// コミット日時（UNIX時間）。履歴はこの順に並べる
+    pub committed_at: i64,
+    pub benchmarks: BTreeMap<String, Record>,
+}
+
+impl Run {
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(7)]
+    }
+}
+
+pub struct History {
+    dir: PathBuf,
+}
+
+/// コミットIDは16進数だけ。それ以外はファイル名に使わない
+pub fn is_valid_commit(commit: &str) -> bool {
+    (4..=64).contains(&commit.len()) && commit.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl History {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(History { dir: dir.to_path_buf() })
+    }
+
+    fn path(&self, commit: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", commit))
+    }
+
+    /// 古いものから順に、すべてのコミットの結果
+    pub fn runs(&self) -> io::Result<Vec<Run>> {
+        let mut runs = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = std::fs::read(&path)?;
+            let run: Run = serde_json::from_slice(&bytes)
+                .map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?;
+            runs.push(run);
+        }
+        runs.sort_by(|a, b| a.committed_at.cmp(&b.committed_at).then_with(|| a.commit.cmp(&b.commit)));
+        Ok(runs)
+    }
+
+    /// 結果を追加する。同じコミットに同じIDがあれば置き換える
+    pub fn ingest(&self, commit: &str, committed_at: i64, benchmarks: BTreeMap<String, Record>) -> io::Result<Run> {
+        if !is_valid_commit(commit) {
+            return Err(invalid_data(format!("invalid commit id `{}`", commit)));
+        }
+        let path = self.path(commit);
+        let mut run = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Run {
+                commit: commit.to_string(),
+                committed_at,
+                benchmarks: BTreeMap::new(),
+            },
+            Err(e) => return Err(e),
+        };
+        run.benchmarks.extend(benchmarks);
+        // 書きかけのファイルを読まれないよう、別名で書いてから置き換える
+        let tmp = self.dir.join(format!("{}.json.tmp", commit));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&run)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(run)
+    }
+}
+
+/// コミットIDの先頭部分で探す。見つからない、または1つに決まらなければエラー
+pub fn find_run(runs: &[Run], prefix: &str) -> Result<usize, String> {
+    let matches: Vec<usize> = (0..runs.len()).filter(|&i| runs[i].commit.starts_with(prefix)).collect();
+    match matches[..] {
+        [index] => Ok(index),
+        [] => Err(format!("no results recorded for commit `{}`", prefix)),
+        _ => Err(format!("commit `{}` is ambiguous", prefix)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn record(samples: &[f64]) -> Record {
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        Record {
+            mean_ns: mean,
+            ci_low_ns: mean * 0.95,
+            ci_high_ns: mean * 1.05,
+            median_ns: mean,
+            stddev_ns: 0.0,
+            samples_ns: samples.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_ingest_merges_and_orders_by_commit_time() {
+        let dir = std::env::temp_dir().join(format!("bench-history-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let history = History::open(&dir).unwrap();
+
+        let first = BTreeMap::from([("a/fib/10".to_string(), record(&[10.0, 11.0]))]);
+        history.ingest("bbbb0000", 200, first).unwrap();
+        history.ingest("aaaa0000", 100, BTreeMap::new()).unwrap();
+        let second = BTreeMap::from([
+            ("a/fib/10".to_string(), record(&[12.0, 13.0])),
+            ("b/sum/1000".to_string(), record(&[1.0, 1.0])),
+        ]);
+        history.ingest("bbbb0000", 200, second).unwrap();
+
+        let runs = history.runs().unwrap();
+        assert_eq!(runs.iter().map(|r| r.commit.as_str()).collect::<Vec<_>>(), ["aaaa0000", "bbbb0000"]);
+        assert_eq!(runs[1].benchmarks.len(), 2);
+        assert_eq!(runs[1].benchmarks["a/fib/10"].mean_ns, 12.5);
+
+        assert_eq!(find_run(&runs, "bbbb"), Ok(1));
+        assert!(find_run(&runs, "cccc").is_err());
+        assert!(history.ingest("../etc", 0, BTreeMap::new()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+// 履歴の推移を1ファイルのHTMLにする
+//
+// 外部のCSSやスクリプトを読まないので、CIの成果物としてそのまま置いておける。
+// グラフはインラインのSVGで、点は平均、縦線は平均の95%信頼区間。
+// 直前の結果から回帰した点は赤、改善した点は緑で示す。
+
+use bench_harness::format_ns;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::compare::{self, Change, Thresholds, Verdict};
+use crate::history::{Record, Run};
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+td.num { text-align: right; }
+.muted { color: #777; }
+.regressed { color: #d62728; font-weight: bold; }
+.improved { color: #2ca02c; }";
+
+// グラフの大きさと余白
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 220.0;
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 20.0;
+const TOP: f64 = 15.0;
+const BOTTOM: f64 = 40.0;
+// これより点が多ければ、コミットのラベルを間引く
+const MAX_LABELS: usize = 12;
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 1つのベンチマークの1コミット分の点
+struct Point<'a> {
+    run: &'a Run,
+    record: &'a Record,
+    /// 直前の点との比較
+    change: Option<Change>,
+}
+
+// ベンチマークごとに、結果のあるコミットを古い順に集める
+fn series(runs: &[Run], thresholds: Thresholds) -> BTreeMap<&str, Vec<Point<'_>>> {
+    let mut series: BTreeMap<&str, Vec<Point>> = BTreeMap::new();
+    for run in runs {
+        for (benchmark, record) in &run.benchmarks {
+            let points = series.entry(benchmark.as_str()).or_default();
+            let change = points.last().map(|previous| compare::compare(previous.record, record, thresholds));
+            points.push(Point { run, record, change });
+        }
+    }
+    series
+}
+
+fn cell_class(change: Option<Change>) -> &'static str {
+    match change.map(|c| c.verdict) {
+        Some(Verdict::Regressed) => "num regressed",
+        Some(Verdict::Improved) => "num improved",
+        _ => "num",
+    }
+}
+
+fn describe_change(change: Option<Change>) -> String {
+    match change {
+        Some(change) => format!("{:+.1}%", change.ratio * 100.0),
+        None => "-".to_string(),
+    }
+}
+
+fn trend_chart(points: &[Point]) -> String {
+    let low = points.iter().map(|p| p.record.ci_low_ns).fold(f64::INFINITY, f64::min);
+    let high = points.iter().map(|p| p.record.ci_high_ns).fold(f64::NEG_INFINITY, f64::max);
+    let padding = if high > low { (high - low) * 0.1 } else { high.abs().max(1.0) * 0.1 };
+    let (low, high) = ((low - padding).max(0.0), high + padding);
+    let plot_width = WIDTH - LEFT - RIGHT;
+    let plot_height = HEIGHT - TOP - BOTTOM;
+    let x = |i: usize| {
+        if points.len() == 1 {
+            LEFT + plot_width / 2.0
+        } else {
+            LEFT + plot_width * i as f64 / (points.len() - 1) as f64
+        }
+    };
+    let y = |ns: f64| TOP + plot_height * (1.0 - (ns - low) / (high - low));
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-size=\"11\">",
+        w = WIDTH,
+        h = HEIGHT
+    )
+    .unwrap();
+    // 軸と目盛り（下端・中央・上端）
+    writeln!(
+        svg,
+        "<polyline points=\"{l},{t} {l},{b} {r},{b}\" fill=\"none\" stroke=\"#999\"/>",
+        l = LEFT,
+        t = TOP,
+        b = HEIGHT - BOTTOM,
+        r = WIDTH - RIGHT
+    )
+    .unwrap();
+    for ns in [low, (low + high) / 2.0, high] {
+        writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\" fill=\"#555\">{}</text>",
+            LEFT - 6.0,
+            y(ns),
+            escape(&format_ns(ns))
+        )
+        .unwrap();
+    }
+    // 信頼区間
+    for (i, point) in points.iter().enumerate() {
+        writeln!(
+            svg,
+            "<line x1=\"{x:.1}\" y1=\"{:.1}\" x2=\"{x:.1}\" y2=\"{:.1}\" stroke=\"#9ecae1\" stroke-width=\"3\"/>",
+            y(point.record.ci_low_ns),
+            y(point.record.ci_high_ns),
+            x = x(i)
+        )
+        .unwrap();
+    }
+    let line: Vec<String> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("{:.1},{:.1}", x(i), y(p.record.mean_ns)))
+        .collect();
+    writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"#1f77b4\"/>", line.join(" ")).unwrap();
+    // 平均の点とコミットのラベル
+    let label_every = points.len().div_ceil(MAX_LABELS);
+    for (i, point) in points.iter().enumerate() {
+        let color = match point.change.map(|c| c.verdict) {
+            Some(Verdict::Regressed) => "#d62728",
+            Some(Verdict::Improved) => "#2ca02c",
+            _ => "#1f77b4",
+        };
+        writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" fill=\"{}\"><title>{} {} ({})</title></circle>",
+            x(i),
+            y(point.record.mean_ns),
+            color,
+            escape(point.run.short_commit()),
+            escape(&format_ns(point.record.mean_ns)),
+            describe_change(point.change)
+        )
+        .unwrap();
+        if i % label_every == 0 || i == points.len() - 1 {
+            writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"#555\">{}</text>",
+                x(i),
+                HEIGHT - BOTTOM + 16.0,
+                escape(point.run.short_commit())
+            )
+            .unwrap();
+        }
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+pub fn render(runs: &[Run], thresholds: Thresholds) -> String {
+    let series = series(runs, thresholds);
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>Benchmark history</title>").unwrap();
+    writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(out, "<h1>Benchmark history</h1>").unwrap();
+    writeln!(
+        out,
+        "<p class=\"muted\">{} commits, {} benchmarks. Changes larger than {:.0}% with p &lt; {} are marked.</p>",
+        runs.len(),
+        series.len(),
+        thresholds.noise * 100.0,
+        thresholds.alpha
+    )
+    .unwrap();
+
+    // 最新の結果の一覧
+    writeln!(out, "<table>\n<tr><th>Benchmark</th><th>Latest</th><th>Commit</th><th>Change</th><th>Results</th></tr>").unwrap();
+    for (benchmark, points) in &series {
+        let latest = points.last().unwrap();
+        writeln!(
+            out,
+            "<tr><td><a href=\"#{id}\">{name}</a></td><td class=\"num\">{}</td><td>{}</td><td class=\"{}\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&format_ns(latest.record.mean_ns)),
+            escape(latest.run.short_commit()),
+            cell_class(latest.change),
+            describe_change(latest.change),
+            points.len(),
+            id = escape(benchmark),
+            name = escape(benchmark)
+        )
+        .unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    for (benchmark, points) in &series {
+        writeln!(out, "<h2 id=\"{}\">{}</h2>", escape(benchmark), escape(benchmark)).unwrap();
+        out.push_str(&trend_chart(points));
+    }
+    writeln!(out, "</body>\n</html>").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::tests::record;
+
+    #[test]
+    fn test_report_marks_regressions() {
+        let steady = [100.0, 101.0, 99.0, 100.5, 99.5, 100.0];
+        let slow: Vec<f64> = steady.iter().map(|x| x * 1.5).collect();
+        let run = |commit: &str, at: i64, samples: &[f64]| Run {
+            commit: commit.to_string(),
+            committed_at: at,
+            benchmarks: BTreeMap::from([("app/<parse>".to_string(), record(samples))]),
+        };
+        let runs = [run("aaaaaaaa11", 1, &steady), run("bbbbbbbb22", 2, &slow)];
+        let html = render(&runs, Thresholds::default());
+
+        assert!(html.contains("<h2 id=\"app/&lt;parse&gt;\">app/&lt;parse&gt;</h2>"));
+        assert!(html.contains("<td class=\"num regressed\">+50.0%</td>"));
+        assert!(html.contains("fill=\"#d62728\""));
+        assert!(html.contains(">bbbbbbb</text>"));
+        // 外部のファイルを読まない
+        assert!(!html.contains("src="));
+    }
+}
@@ -0,0 +1,276 @@
+// criterionのベンチマーク結果をコミットごとに記録し、回帰を検出する
+//
+//   cargo bench                                   # 各クレートで実行
+//   bench-history ingest ../../chapter-01/hands-on-06/rust
+//   bench-history check                           # 回帰があれば終了コード1
+//   bench-history report --out history.html
+
+mod compare;
+mod criterion;
+mod git;
+mod history;
+mod html;
+mod stats;
+
+use bench_harness::format_ns;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use compare::{Thresholds, Verdict};
+use history::History;
+
+const USAGE: &str = "Usage: bench-history [--db <DIR>] ingest [--commit <REV>] [--project <NAME>] <CRITERION_DIR>...
+       bench-history [--db <DIR>] check [--commit <COMMIT>] [--baseline <COMMIT>] [--threshold <PERCENT>] [--alpha <P>]
+       bench-history [--db <DIR>] report [--out <FILE>] [--threshold <PERCENT>] [--alpha <P>]
+       bench-history [--db <DIR>] list
+
+  --db <DIR>             history database directory (default: .bench-history)
+
+  ingest                 record the latest `cargo bench` results of each directory
+                         (a crate directory or its target/criterion); results written
+                         more than 10 minutes before the rest of the latest run are
+                         left over from earlier runs and skipped with a warning
+  --commit <REV>         commit to record the results under (default: HEAD of the
+                         repository containing the directory)
+  --project <NAME>       prefix for benchmark ids (default: the crate directory name)
+
+  check                  compare a commit with a baseline and exit with status 1
+                         if any benchmark regressed
+  --commit <COMMIT>      commit to check, by id prefix (default: the latest one)
+  --baseline <COMMIT>    commit to compare with (default: the previous commit that
+                         has the same benchmark)
+  --threshold <PERCENT>  ignore changes of the mean smaller than this (default: 5)
+  --alpha <P>            significance level of Welch's t-test (default: 0.05)
+
+  report                 write the trend of every benchmark as a static HTML page
+  --out <FILE>           output file (default: bench-history.html)
+
+  list                   show the recorded commits";
+
+enum Command {
+    Ingest {
+        commit: String,
+        project: Option<String>,
+        dirs: Vec<PathBuf>,
+    },
+    Check {
+        commit: Option<String>,
+        baseline: Option<String>,
+    },
+    Report {
+        out: PathBuf,
+    },
+    List,
+}
+
+struct Options {
+    db: PathBuf,
+    thresholds: Thresholds,
+    command: Command,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<f64, String> {
+    let value = value.ok_or(USAGE)?;
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+        _ => Err(format!("{} expects a non-negative number, got `{}`", flag, value)),
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut db = PathBuf::from(".bench-history");
+    let mut thresholds = Thresholds::default();
+    let mut name = None;
+    let mut commit = None;
+    let mut baseline = None;
+    let mut project = None;
+    let mut out = None;
+    let mut dirs = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => db = PathBuf::from(args.next().ok_or(USAGE)?),
+            "--commit" => commit = Some(args.next().ok_or(USAGE)?),
+            "--baseline" => baseline = Some(args.next().ok_or(USAGE)?),
+            "--project" => project = Some(args.next().ok_or(USAGE)?),
+            "--out" => out = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--threshold" => thresholds.noise = parse_number("--threshold", args.next())? / 100.0,
+            "--alpha" => thresholds.alpha = parse_number("--alpha", args.next())?,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            flag if flag.starts_with('-') => return Err(USAGE.to_string()),
+            _ if name.is_none() => name = Some(arg),
+            _ => dirs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if project.is_some() && !(name.as_deref() == Some("ingest") && dirs.len() == 1) {
+        return Err("--project can only be used when ingesting a single directory".to_string());
+    }
+    let command = match name.as_deref() {
+        Some("ingest") if !dirs.is_empty() => Command::Ingest {
+            commit: commit.unwrap_or_else(|| "HEAD".to_string()),
+            project,
+            dirs,
+        },
+        Some("check") if dirs.is_empty() => Command::Check { commit, baseline },
+        Some("report") if dirs.is_empty() => Command::Report {
+            out: out.unwrap_or_else(|| PathBuf::from("bench-history.html")),
+        },
+        Some("list") if dirs.is_empty() => Command::List,
+        _ => return Err(USAGE.to_string()),
+    };
+    Ok(Options { db, thresholds, command })
+}
+
+fn ingest(history: &History, rev: &str, project: Option<&str>, dirs: &[PathBuf]) -> Result<(), String> {
+    // すべて読めてから記録する（一部だけ記録されないように）
+    let mut benchmarks = BTreeMap::new();
+    let mut commit: Option<git::Commit> = None;
+    for dir in dirs {
+        let output_dir = criterion::output_dir(dir);
+        let found = git::resolve(&output_dir, rev)?;
+        if let Some(previous) = &commit {
+            if previous.id != found.id {
+                return Err(format!("{} is at a different commit ({}) than the other directories", dir.display(), found.id));
+            }
+        }
+        if git::is_dirty(&output_dir) {
+            eprintln!("warning: {} has uncommitted changes; recording under {} anyway", dir.display(), found.id);
+        }
+        let project = project.map_or_else(|| criterion::project_name(&output_dir), str::to_string);
+        let latest = criterion::read(&output_dir).map_err(|e| e.to_string())?;
+        if !latest.stale.is_empty() {
+            eprintln!(
+                "warning: {}: skipping {} benchmarks left from an earlier run: {}",
+                output_dir.display(),
+                latest.stale.len(),
+                latest.stale.join(", ")
+            );
+        }
+        let results = latest.benchmarks;
+        println!("{}: {} benchmarks as `{}`", output_dir.display(), results.len(), project);
+        benchmarks.extend(results.into_iter().map(|(id, record)| (format!("{}/{}", project, id), record)));
+        commit = Some(found);
+    }
+    let commit = commit.expect("at least one directory");
+    let run = history
+        .ingest(&commit.id, commit.committed_at, benchmarks)
+        .map_err(|e| e.to_string())?;
+    println!("recorded {} benchmarks for {}", run.benchmarks.len(), run.short_commit());
+    Ok(())
+}
+
+// 回帰があれば `Ok(false)`
+fn check(history: &History, commit: Option<&str>, baseline: Option<&str>, thresholds: Thresholds) -> Result<bool, String> {
+    let runs = history.runs().map_err(|e| e.to_string())?;
+    if runs.is_empty() {
+        return Err("the history is empty; run `bench-history ingest` first".to_string());
+    }
+    let target = match commit {
+        Some(prefix) => history::find_run(&runs, prefix)?,
+        None => runs.len() - 1,
+    };
+    let baseline = baseline.map(|prefix| history::find_run(&runs, prefix)).transpose()?;
+    let comparisons = compare::compare_runs(&runs, target, baseline, thresholds);
+
+    let width = comparisons.iter().map(|c| c.benchmark.len()).max().unwrap_or(0);
+    println!("{} compared with {}", runs[target].short_commit(), baseline.map_or("the previous results", |i| runs[i].short_commit()));
+    for comparison in &comparisons {
+        let (Some(change), Some(before), Some(base)) = (comparison.change, comparison.before_ns, &comparison.baseline) else {
+            println!("  {:<9}  {:<width$}  {:>10}  (no baseline)", "new", comparison.benchmark, format_ns(comparison.after_ns));
+            continue;
+        };
+        let p = change.p.map_or_else(|| "p=n/a".to_string(), |p| format!("p={:.3}", p));
+        println!(
+            "  {:<9}  {:<width$}  {:>10} -> {:>10}  {:>+7.1}%  {}  ({})",
+            change.verdict.label(),
+            comparison.benchmark,
+            format_ns(before),
+            format_ns(comparison.after_ns),
+            change.ratio * 100.0,
+            p,
+            &base[..base.len().min(7)]
+        );
+    }
+    let regressed = compare::count(&comparisons, Verdict::Regressed);
+    println!(
+        "{} regressed, {} improved, {} unchanged, {} without baseline",
+        regressed,
+        compare::count(&comparisons, Verdict::Improved),
+        compare::count(&comparisons, Verdict::Unchanged),
+        comparisons.iter().filter(|c| c.change.is_none()).count()
+    );
+    Ok(regressed == 0)
+}
+
+fn report(history: &History, out: &Path, thresholds: Thresholds) -> Result<(), String> {
+    let runs = history.runs().map_err(|e| e.to_string())?;
+    std::fs::write(out, html::render(&runs, thresholds)).map_err(|e| format!("{}: {}", out.display(), e))?;
+    println!("wrote {} ({} commits)", out.display(), runs.len());
+    Ok(())
+}
+
+fn list(history: &History) -> Result<(), String> {
+    for run in history.runs().map_err(|e| e.to_string())? {
+        println!("{}  {:>12}  {} benchmarks", run.short_commit(), run.committed_at, run.benchmarks.len());
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let result = History::open(&options.db)
+        .map_err(|e| format!("{}: {}", options.db.display(), e))
+        .and_then(|history| match &options.command {
+            Command::Ingest { commit, project, dirs } => ingest(&history, commit, project.as_deref(), dirs).map(|()| true),
+            Command::Check { commit, baseline } => {
+                check(&history, commit.as_deref(), baseline.as_deref(), options.thresholds)
+            }
+            Command::Report { out } => report(&history, out, options.thresholds).map(|()| true),
+            Command::List => list(&history).map(|()| true),
+        });
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = args("--db hist check --threshold 2 --baseline abc1").unwrap();
+        assert_eq!(options.db, PathBuf::from("hist"));
+        assert_eq!(options.thresholds, Thresholds { alpha: 0.05, noise: 0.02 });
+        assert!(matches!(options.command, Command::Check { commit: None, baseline: Some(ref b) } if b == "abc1"));
+
+        let options = args("ingest a b").unwrap();
+        assert!(matches!(options.command, Command::Ingest { ref commit, ref dirs, .. } if commit == "HEAD" && dirs.len() == 2));
+
+        assert!(args("ingest").is_err());
+        assert!(args("check extra").is_err());
+        assert!(args("ingest --project x a b").is_err());
+        assert!(args("check --alpha -1").is_err());
+        assert!(args("frobnicate").is_err());
+    }
+}
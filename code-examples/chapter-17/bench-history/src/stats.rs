@@ -0,0 +1,158 @@
+// 2つの計測結果の差が有意かどうか
+//
+// サンプル（1回あたりの時間）の分散が等しいとは限らないので、Welchのt検定を使う。
+// p値はt分布の累積分布関数から求める（正則化不完全ベータ関数で計算する）。
+
+/// Welchのt検定の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TTest {
+    /// 正なら `after` のほうが大きい（遅い）
+    pub t: f64,
+    pub degrees_of_freedom: f64,
+    /// 両側のp値
+    pub p: f64,
+}
+
+fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// 標本がそれぞれ2つ以上なければ `None`
+pub fn welch_t_test(before: &[f64], after: &[f64]) -> Option<TTest> {
+    if before.len() < 2 || after.len() < 2 {
+        return None;
+    }
+    let (mean_a, var_a) = mean_and_variance(before);
+    let (mean_b, var_b) = mean_and_variance(after);
+    let (se_a, se_b) = (var_a / before.len() as f64, var_b / after.len() as f64);
+    let se = se_a + se_b;
+    if se == 0.0 {
+        // ばらつきがまったくなければ、平均が違うかどうかだけで決まる
+        let p = if mean_a == mean_b { 1.0 } else { 0.0 };
+        let t = match mean_b.partial_cmp(&mean_a) {
+            Some(std::cmp::Ordering::Greater) => f64::INFINITY,
+            Some(std::cmp::Ordering::Less) => f64::NEG_INFINITY,
+            _ => 0.0,
+        };
+        return Some(TTest { t, degrees_of_freedom: f64::INFINITY, p });
+    }
+    let t = (mean_b - mean_a) / se.sqrt();
+    let degrees_of_freedom =
+        se.powi(2) / (se_a.powi(2) / (before.len() - 1) as f64 + se_b.powi(2) / (after.len() - 1) as f64);
+    Some(TTest {
+        t,
+        degrees_of_freedom,
+        p: two_sided_p(t, degrees_of_freedom),
+    })
+}
+
+/// 自由度 `df` のt分布で |T| >= |t| となる確率
+pub fn two_sided_p(t: f64, df: f64) -> f64 {
+    regularized_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+// ln Γ(x)（Lanczos近似、x > 0）
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // 反射公式
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// 正則化不完全ベータ関数 I_x(a, b)
+fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // 連分数が速く収束する側で計算する
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Lentzの方法で連分数を評価する
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-15;
+    let nonzero = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / nonzero(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        // 偶数項
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / nonzero(1.0 + numerator * d);
+        c = nonzero(1.0 + numerator / c);
+        h *= d * c;
+        // 奇数項
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / nonzero(1.0 + numerator * d);
+        c = nonzero(1.0 + numerator / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_sided_p_matches_t_table() {
+        // t分布表の値（両側）
+        assert!((two_sided_p(2.228, 10.0) - 0.05).abs() < 1e-4);
+        assert!((two_sided_p(2.0, 10.0) - 0.073_39).abs() < 1e-4);
+        assert!((two_sided_p(12.706, 1.0) - 0.05).abs() < 1e-4);
+        assert!((two_sided_p(1.96, 1e6) - 0.05).abs() < 1e-4);
+        assert_eq!(two_sided_p(0.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        let before = [10.0, 10.2, 9.9, 10.1, 9.8, 10.0];
+        let after = [11.0, 11.1, 10.9, 11.2, 10.8, 11.0];
+        let test = welch_t_test(&before, &after).unwrap();
+        assert!(test.t > 0.0);
+        assert!(test.p < 1e-6, "{:?}", test);
+
+        let same = welch_t_test(&before, &before).unwrap();
+        assert_eq!(same.t, 0.0);
+        assert!((same.p - 1.0).abs() < 1e-12);
+
+        assert_eq!(welch_t_test(&[5.0, 5.0], &[6.0, 6.0]).unwrap().p, 0.0);
+        assert!(welch_t_test(&[1.0], &after).is_none());
+    }
+}
@@ -1,7 +1,7 @@
-use std::fmt::Write;
-
 //! ユーザー情報のリストをフォーマットする、いくつかのバージョンの関数。
 
+use std::fmt::Write;
+
 /// 素朴な実装。
 /// ループ内で毎回新しいStringを確保する。
 pub fn format_user_list_naive(users: &[(u32, &str)]) -> Vec<String> {
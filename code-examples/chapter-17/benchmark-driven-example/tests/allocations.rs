@@ -0,0 +1,28 @@
+// ホットパスの割り当て回数を固定するテスト
+//
+// 割り当てを減らした実装が、後の変更で元に戻ってしまわないようにする。
+
+use benchmark_driven_example::{format_user_list_naive, format_user_list_single_string};
+use tracking_allocator::{assert_max_allocations, TrackingAllocator};
+
+#[global_allocator]
+static GLOBAL: TrackingAllocator = TrackingAllocator::new();
+
+fn users() -> Vec<(u32, &'static str)> {
+    (0..1000).map(|id| (id, "alice")).collect()
+}
+
+#[test]
+fn single_string_allocates_once() {
+    let users = users();
+    // 事前に確保したキャパシティに収まるので、出力のStringの1回だけで済む
+    let output = assert_max_allocations!(1, { format_user_list_single_string(&users) });
+    assert_eq!(output.lines().count(), users.len());
+}
+
+#[test]
+#[should_panic(expected = "expected at most")]
+fn naive_allocates_per_user() {
+    let users = users();
+    assert_max_allocations!(1, { format_user_list_naive(&users) });
+}